@@ -17,7 +17,7 @@ pub fn surrounding_box(box0: &AABB, box1: &AABB) -> AABB {
     AABB { min, max }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub struct AABB {
     pub min: Vector3<f64>,
     pub max: Vector3<f64>,
@@ -28,11 +28,33 @@ impl AABB {
         Self { min, max }
     }
 
-    pub fn hit(&self, ray: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
+    pub fn centroid(&self) -> Vector3<f64> {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn area(&self) -> f64 {
+        let d = self.max - self.min;
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        let inv_direction = ray.direction().map(|d| 1.0 / d);
+        self.hit_inverse(&ray.origin(), &inv_direction, t_min, t_max)
+    }
+
+    // same as hit, with the inverse of the ray direction computed by the caller
+    // so that a traversal can reuse it for every box
+    pub fn hit_inverse(
+        &self,
+        origin: &Vector3<f64>,
+        inv_direction: &Vector3<f64>,
+        mut t_min: f64,
+        mut t_max: f64,
+    ) -> bool {
         for a in 0..3 {
-            let inv_d = 1.0 / ray.direction()[a];
-            let t0 = (self.min[a] - ray.origin()[a]) * inv_d;
-            let t1 = (self.max[a] - ray.origin()[a]) * inv_d;
+            let inv_d = inv_direction[a];
+            let t0 = (self.min[a] - origin[a]) * inv_d;
+            let t1 = (self.max[a] - origin[a]) * inv_d;
             let (t0, t1) = if inv_d < 0.0 { (t1, t0) } else { (t0, t1) };
             t_min = t_min.max(t0);
             t_max = t_max.min(t1);
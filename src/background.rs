@@ -0,0 +1,255 @@
+use image::DynamicImage;
+use nalgebra::Vector3;
+use std::f64;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::tonemap::{luminance, srgb_eotf};
+
+/*
+* The radiance that reaches the rays leaving the scene. A color is a
+* constant background, the other backgrounds change with the direction
+*/
+pub trait Background: Send + Sync {
+    fn radiance(&self, ray: &Ray) -> Vector3<f64>;
+
+    // a background that can be sampled is a light of the next-event
+    // estimation, like the emissive hittables
+    fn is_light(&self) -> bool {
+        false
+    }
+
+    // the density over the solid angle of random() returning direction
+    fn pdf_value(&self, _direction: &Vector3<f64>) -> f64 {
+        0.0
+    }
+
+    fn random(&self, _sampler: &mut dyn Sampler) -> Vector3<f64> {
+        Vector3::new(0.0, 1.0, 0.0)
+    }
+}
+
+impl Background for Vector3<f64> {
+    fn radiance(&self, _ray: &Ray) -> Vector3<f64> {
+        *self
+    }
+}
+
+impl<B: Background + ?Sized> Background for Arc<B> {
+    fn radiance(&self, ray: &Ray) -> Vector3<f64> {
+        (**self).radiance(ray)
+    }
+
+    fn is_light(&self) -> bool {
+        (**self).is_light()
+    }
+
+    fn pdf_value(&self, direction: &Vector3<f64>) -> f64 {
+        (**self).pdf_value(direction)
+    }
+
+    fn random(&self, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        (**self).random(sampler)
+    }
+}
+
+// blends from bottom, straight down, to top, straight up
+#[derive(Clone, Copy, Debug)]
+pub struct Gradient {
+    pub bottom: Vector3<f64>,
+    pub top: Vector3<f64>,
+}
+
+impl Gradient {
+    pub fn new(bottom: Vector3<f64>, top: Vector3<f64>) -> Self {
+        Self { bottom, top }
+    }
+
+    // the sky of the first book, white at the horizon and blue above
+    pub fn sky() -> Self {
+        Self::new(Vector3::new(1.0, 1.0, 1.0), Vector3::new(0.5, 0.7, 1.0))
+    }
+}
+
+impl Background for Gradient {
+    fn radiance(&self, ray: &Ray) -> Vector3<f64> {
+        let t = 0.5 * (ray.direction().normalize().y + 1.0);
+        (1.0 - t) * self.bottom + t * self.top
+    }
+}
+
+// a panorama in the equirectangular (latitude-longitude) projection: the
+// columns go around the y axis, starting behind the default camera (+z) so
+// -z is in the middle of the image, the rows go from straight up to
+// straight down. every pixel covers a constant radiance.
+// the pixels are sampled with a probability that follows their power, the
+// luminance times the solid angle they cover: a row is picked with the
+// marginal distribution of the rows, then a pixel of the row
+#[derive(Clone, Debug)]
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    // linear radiance, row by row from the top
+    pixels: Vec<Vector3<f64>>,
+    // probability of every pixel
+    pixel_probability: Vec<f64>,
+    // cumulative distribution of the rows, and of the pixels of every row
+    row_cdf: Vec<f64>,
+    column_cdf: Vec<f64>,
+    // scales the radiance of the image
+    pub intensity: f64,
+    // turns the panorama around the y axis, in degrees
+    pub rotation: f64,
+}
+
+impl EnvironmentMap {
+    pub fn new(width: usize, height: usize, pixels: Vec<Vector3<f64>>) -> Self {
+        assert_eq!(pixels.len(), width * height);
+        let mut power: Vec<f64> = pixels
+            .iter()
+            .enumerate()
+            .map(|(k, p)| luminance(p).max(0.0) * row_solid_angle(k / width, height))
+            .collect();
+        let total: f64 = power.iter().sum();
+        if total > 0.0 {
+            power.iter_mut().for_each(|p| *p /= total);
+        }
+
+        let mut row_cdf = Vec::with_capacity(height);
+        let mut column_cdf = Vec::with_capacity(width * height);
+        let mut rows = 0.0;
+        for row in power.chunks(width) {
+            let row_total: f64 = row.iter().sum();
+            let mut columns = 0.0;
+            for p in row {
+                columns += p;
+                column_cdf.push(if row_total > 0.0 {
+                    columns / row_total
+                } else {
+                    0.0
+                });
+            }
+            rows += row_total;
+            row_cdf.push(rows);
+        }
+        Self {
+            width,
+            height,
+            pixels,
+            pixel_probability: power,
+            row_cdf,
+            column_cdf,
+            intensity: 1.0,
+            rotation: 0.0,
+        }
+    }
+
+    // .hdr and .exr files are linear, the 8 and 16 bit images are decoded
+    // from sRGB
+    pub fn open<P: AsRef<Path>>(path: P) -> image::ImageResult<Self> {
+        let image = image::open(path)?;
+        let linear = matches!(
+            image,
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+        );
+        let image = image.into_rgb32f();
+        let (width, height) = image.dimensions();
+        let pixels = image
+            .pixels()
+            .map(|p| {
+                let color = Vector3::new(p[0] as f64, p[1] as f64, p[2] as f64);
+                if linear {
+                    color
+                } else {
+                    color.map(srgb_eotf)
+                }
+            })
+            .collect();
+        Ok(Self::new(width as usize, height as usize, pixels))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // the column and row of the pixel seen in direction
+    pub fn pixel_at(&self, direction: &Vector3<f64>) -> (usize, usize) {
+        let d = direction.normalize();
+        let phi = d.x.atan2(-d.z) - self.rotation.to_radians();
+        let u = (phi / (2.0 * f64::consts::PI) + 0.5).rem_euclid(1.0);
+        let v = nalgebra::clamp(d.y, -1.0, 1.0).acos() / f64::consts::PI;
+        let i = ((u * self.width as f64) as usize).min(self.width - 1);
+        let j = ((v * self.height as f64) as usize).min(self.height - 1);
+        (i, j)
+    }
+
+    // the direction through the point (u, v) of the image, both in [0, 1]
+    pub fn direction(&self, u: f64, v: f64) -> Vector3<f64> {
+        let phi = (u - 0.5) * 2.0 * f64::consts::PI + self.rotation.to_radians();
+        let theta = v * f64::consts::PI;
+        Vector3::new(
+            theta.sin() * phi.sin(),
+            theta.cos(),
+            -theta.sin() * phi.cos(),
+        )
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> Vector3<f64> {
+        self.pixels[j * self.width + i] * self.intensity
+    }
+}
+
+// the solid angle of the pixels of a row, the sum over the whole image is 4 pi
+fn row_solid_angle(row: usize, height: usize) -> f64 {
+    let theta0 = row as f64 / height as f64 * f64::consts::PI;
+    let theta1 = (row + 1) as f64 / height as f64 * f64::consts::PI;
+    // the width is left out, it is the same for every pixel
+    theta0.cos() - theta1.cos()
+}
+
+// the first index with a cumulative probability above r
+fn search(cdf: &[f64], r: f64) -> usize {
+    cdf.partition_point(|&c| c <= r).min(cdf.len() - 1)
+}
+
+impl Background for EnvironmentMap {
+    fn radiance(&self, ray: &Ray) -> Vector3<f64> {
+        let (i, j) = self.pixel_at(&ray.direction());
+        self.get(i, j)
+    }
+
+    // a black image has nothing to sample
+    fn is_light(&self) -> bool {
+        self.row_cdf[self.height - 1] > 0.0
+    }
+
+    fn pdf_value(&self, direction: &Vector3<f64>) -> f64 {
+        let (i, j) = self.pixel_at(direction);
+        let solid_angle =
+            2.0 * f64::consts::PI / self.width as f64 * row_solid_angle(j, self.height);
+        self.pixel_probability[j * self.width + i] / solid_angle
+    }
+
+    // a pixel with the distribution, then a direction with a uniform density
+    // over the solid angle of the pixel
+    fn random(&self, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        let (r1, r2) = sampler.next_2d();
+        let j = search(&self.row_cdf, r1 * self.row_cdf[self.height - 1]);
+        let row = &self.column_cdf[j * self.width..(j + 1) * self.width];
+        let i = search(row, r2 * row[self.width - 1]);
+
+        let (r3, r4) = sampler.next_2d();
+        let u = (i as f64 + r3) / self.width as f64;
+        let cos0 = (j as f64 / self.height as f64 * f64::consts::PI).cos();
+        let cos1 = ((j + 1) as f64 / self.height as f64 * f64::consts::PI).cos();
+        let cos_theta = cos0 + r4 * (cos1 - cos0);
+        let v = nalgebra::clamp(cos_theta, -1.0, 1.0).acos() / f64::consts::PI;
+        self.direction(u, v)
+    }
+}
@@ -1,94 +1,406 @@
 use crate::aabb;
 use crate::aabb::AABB;
-use crate::hittable::{HitRecord, Hittable};
+use crate::hittable::{self, HitRecord, Hittable};
 use crate::ray::Ray;
-use rand::Rng;
-use std::cmp::Ordering;
-use std::rc::Rc;
+use crate::sampler::Sampler;
+use nalgebra::Vector3;
+use std::sync::Arc;
 
-pub struct BVHNode {
-    // use dyn to make explicit the dynamic dispatch of an object
-    left: Rc<dyn Hittable>,
-    right: Rc<dyn Hittable>,
+// number of buckets used to evaluate the candidate split planes on each axis
+const BINS: usize = 12;
+// cost of visiting a node, relative to the cost of intersecting a primitive
+const TRAVERSAL_COST: f64 = 0.125;
+const INTERSECTION_COST: f64 = 1.0;
+// a leaf never holds more primitives than this, unless they can't be split
+const MAX_LEAF_SIZE: usize = 4;
+// the traversal keeps the nodes still to visit in a fixed size stack,
+// past this depth the builder stops splitting
+const MAX_DEPTH: usize = 64;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BVHStats {
+    pub depth: usize,
+    pub node_count: usize,
+    pub leaf_count: usize,
+    // expected cost of a random ray, measured with the surface area heuristic
+    pub cost: f64,
+}
+
+// a primitive with its bounding box computed once for the whole build
+struct Primitive {
+    index: usize,
     bbox: AABB,
+    centroid: Vector3<f64>,
 }
 
-impl BVHNode {
-    pub fn new(hittable: &mut [Rc<dyn Hittable>], time0: f64, time1: f64) -> Self {
-        // FnMut trait is used for types that can be called as they were functions
-        // and are mutable (in this case)
-        fn box_compare(
-            time0: f64,
-            time1: f64,
-            axis: usize,
-        ) -> impl FnMut(&Rc<dyn Hittable>, &Rc<dyn Hittable>) -> Ordering {
-            // move converts any variables captured by reference or mutable reference to variables captured by value.
-            move |a, b| {
-                let a_bbox = a.bounding_box(time0, time1);
-                let b_bbox = b.bounding_box(time0, time1);
-                if a_bbox.is_none() || b_bbox.is_none() {
-                    panic!["No bounding box in BVHNode"]
-                }
-                if a_bbox.unwrap().min[axis] - b_bbox.unwrap().min[axis] < 0.0 {
-                    Ordering::Less
-                } else {
-                    Ordering::Greater
-                }
+fn surrounding(primitives: &[Primitive]) -> AABB {
+    primitives
+        .iter()
+        .skip(1)
+        .fold(primitives[0].bbox, |acc, p| {
+            aabb::surrounding_box(&acc, &p.bbox)
+        })
+}
+
+fn bin_index(centroid: f64, min: f64, extent: f64) -> usize {
+    let b = ((centroid - min) / extent * BINS as f64) as usize;
+    b.min(BINS - 1)
+}
+
+struct Split {
+    axis: usize,
+    bin: usize,
+    min: f64,
+    extent: f64,
+    // SAH cost of the split, assuming both sides become leaves
+    cost: f64,
+}
+
+// returns the axis and the bin where the split with the lowest SAH cost happens,
+// None when all the centroids are in the same point
+fn find_split(primitives: &[Primitive], bbox: &AABB) -> Option<Split> {
+    let first = primitives[0].centroid;
+    let (cmin, cmax) = primitives.iter().fold((first, first), |(min, max), p| {
+        (min.inf(&p.centroid), max.sup(&p.centroid))
+    });
+
+    let mut best: Option<Split> = None;
+    for axis in 0..3 {
+        let extent = cmax[axis] - cmin[axis];
+        if extent <= 0.0 {
+            continue;
+        }
+        let mut counts = [0usize; BINS];
+        let mut bounds: [Option<AABB>; BINS] = [None; BINS];
+        for p in primitives {
+            let b = bin_index(p.centroid[axis], cmin[axis], extent);
+            counts[b] += 1;
+            bounds[b] = Some(match bounds[b] {
+                Some(bbox) => aabb::surrounding_box(&bbox, &p.bbox),
+                None => p.bbox,
+            });
+        }
+        // sweep from the right to know the area and count of every right side
+        let mut right_area = [0.0; BINS];
+        let mut right_count = [0usize; BINS];
+        let mut acc: Option<AABB> = None;
+        let mut count = 0;
+        for b in (1..BINS).rev() {
+            acc = merge(acc, bounds[b]);
+            count += counts[b];
+            right_area[b] = acc.map_or(0.0, |bbox| bbox.area());
+            right_count[b] = count;
+        }
+        let mut acc: Option<AABB> = None;
+        let mut count = 0;
+        for b in 1..BINS {
+            acc = merge(acc, bounds[b - 1]);
+            count += counts[b - 1];
+            if count == 0 || right_count[b] == 0 {
+                continue;
             }
+            let left_area = acc.map_or(0.0, |bbox| bbox.area());
+            let below = left_area * count as f64 + right_area[b] * right_count[b] as f64;
+            let cost = TRAVERSAL_COST + INTERSECTION_COST * area_ratio(below, bbox.area());
+            let better = match &best {
+                Some(split) => cost < split.cost,
+                None => true,
+            };
+            if better {
+                best = Some(Split {
+                    axis,
+                    bin: b,
+                    min: cmin[axis],
+                    extent,
+                    cost,
+                });
+            }
+        }
+    }
+    best
+}
+
+fn merge(acc: Option<AABB>, bbox: Option<AABB>) -> Option<AABB> {
+    match (acc, bbox) {
+        (Some(a), Some(b)) => Some(aabb::surrounding_box(&a, &b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+// a flat box (or a point) has no area, every ray reaching the parent is
+// assumed to reach the child too
+fn area_ratio(child: f64, parent: f64) -> f64 {
+    if parent > 0.0 {
+        child / parent
+    } else {
+        1.0
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct LinearNode {
+    bbox: AABB,
+    // index of the first primitive for a leaf, index of the second child for
+    // an interior node (the first child always follows its parent)
+    offset: usize,
+    // number of primitives, zero for an interior node
+    count: usize,
+    // axis of the split, used to visit the nearest child first
+    axis: usize,
+}
+
+// the nodes of the tree are stored depth first in a single array, so the
+// traversal walks through contiguous memory instead of chasing pointers
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BVHTree {
+    nodes: Vec<LinearNode>,
+    stats: BVHStats,
+}
+
+impl BVHTree {
+    // builds the tree over the given bounding boxes with the surface area
+    // heuristic: every node is split on the axis and the plane that minimize
+    // the expected cost of a ray, so the same boxes always give the same tree.
+    // the returned order tells how the primitives must be rearranged, the
+    // leaves refer to contiguous ranges of that order
+    pub(crate) fn build(boxes: &[AABB]) -> (Self, Vec<usize>) {
+        let mut tree = Self::default();
+        if boxes.is_empty() {
+            return (tree, Vec::new());
         }
+        let mut primitives: Vec<Primitive> = boxes
+            .iter()
+            .enumerate()
+            .map(|(index, bbox)| Primitive {
+                index,
+                bbox: *bbox,
+                centroid: bbox.centroid(),
+            })
+            .collect();
+        let mut order = Vec::with_capacity(boxes.len());
+        tree.stats = tree.build_node(&mut primitives, &mut order, 1);
+        (tree, order)
+    }
 
-        let axis = rand::thread_rng().gen_range(0..3) as usize;
+    fn build_node(
+        &mut self,
+        primitives: &mut [Primitive],
+        order: &mut Vec<usize>,
+        depth: usize,
+    ) -> BVHStats {
+        let bbox = surrounding(primitives);
+        let len = primitives.len();
+        let node_index = self.nodes.len();
+        self.nodes.push(LinearNode {
+            bbox,
+            offset: 0,
+            count: 0,
+            axis: 0,
+        });
 
-        hittable.sort_unstable_by(box_compare(time0, time1, axis));
-        let len = hittable.len();
-        let (left, right) = if len == 1 {
-            (hittable[0].clone(), hittable[0].clone())
-        } else if len == 2 {
-            (hittable[0].clone(), hittable[1].clone())
+        let leaf_cost = INTERSECTION_COST * len as f64;
+        let split = if len == 1 || depth >= MAX_DEPTH {
+            None
         } else {
-            (
-                Rc::new(BVHNode::new(&mut hittable[0..len / 2], time0, time1)) as Rc<dyn Hittable>,
-                Rc::new(BVHNode::new(&mut hittable[len / 2..len], time0, time1))
-                    as Rc<dyn Hittable>,
-            )
+            find_split(primitives, &bbox)
         };
-        let left_bbox = left.bounding_box(time0, time1);
-        let right_box = right.bounding_box(time0, time1);
-        if left_bbox.is_none() || right_box.is_none() {
-            panic!["No bounding box in BVHNode"]
+        let mid = match split {
+            Some(split) if len > MAX_LEAF_SIZE || split.cost < leaf_cost => {
+                let axis = split.axis;
+                // sort_by is stable, primitives that share a centroid keep their order
+                primitives.sort_by(|a, b| a.centroid[axis].total_cmp(&b.centroid[axis]));
+                self.nodes[node_index].axis = axis;
+                primitives
+                    .iter()
+                    .take_while(|p| {
+                        bin_index(p.centroid[axis], split.min, split.extent) < split.bin
+                    })
+                    .count()
+            }
+            // all the centroids are in the same point, no plane can separate
+            // them: the biggest sets are cut in half anyway
+            None if len > MAX_LEAF_SIZE && depth < MAX_DEPTH => len / 2,
+            _ => {
+                self.nodes[node_index].offset = order.len();
+                self.nodes[node_index].count = len;
+                order.extend(primitives.iter().map(|p| p.index));
+                return BVHStats {
+                    depth: 1,
+                    node_count: 1,
+                    leaf_count: 1,
+                    cost: leaf_cost,
+                };
+            }
+        };
+
+        let (left_primitives, right_primitives) = primitives.split_at_mut(mid);
+        let left_box = surrounding(left_primitives);
+        let right_box = surrounding(right_primitives);
+        let left = self.build_node(left_primitives, order, depth + 1);
+        self.nodes[node_index].offset = self.nodes.len();
+        let right = self.build_node(right_primitives, order, depth + 1);
+
+        let below = left_box.area() * left.cost + right_box.area() * right.cost;
+        BVHStats {
+            depth: 1 + left.depth.max(right.depth),
+            node_count: 1 + left.node_count + right.node_count,
+            leaf_count: left.leaf_count + right.leaf_count,
+            cost: TRAVERSAL_COST + area_ratio(below, bbox.area()),
         }
+    }
+
+    pub(crate) fn stats(&self) -> BVHStats {
+        self.stats
+    }
+
+    pub(crate) fn bounding_box(&self) -> Option<AABB> {
+        self.nodes.first().map(|node| node.bbox)
+    }
+
+    // walks the tree front to back: at every interior node the child on the
+    // side the ray comes from is visited first, and the other one is tested
+    // later against the closest hit found so far. hit_primitive receives the
+    // position of a primitive in the build order and the current t_max
+    pub(crate) fn hit<'a, F>(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        mut hit_primitive: F,
+    ) -> Option<HitRecord<'a>>
+    where
+        F: FnMut(usize, f64) -> Option<HitRecord<'a>>,
+    {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let origin = ray.origin();
+        let inv_direction = ray.direction().map(|d| 1.0 / d);
+
+        let mut closest_so_far = t_max;
+        let mut hit_anything: Option<HitRecord> = None;
+        let mut stack = [0usize; MAX_DEPTH];
+        let mut stack_size = 0;
+        let mut current = 0;
+        loop {
+            let node = &self.nodes[current];
+            if node
+                .bbox
+                .hit_inverse(&origin, &inv_direction, t_min, closest_so_far)
+            {
+                if node.count > 0 {
+                    for i in node.offset..node.offset + node.count {
+                        if let Some(hit) = hit_primitive(i, closest_so_far) {
+                            closest_so_far = hit.t;
+                            hit_anything = Some(hit);
+                        }
+                    }
+                } else {
+                    let (near, far) = if inv_direction[node.axis] < 0.0 {
+                        (node.offset, current + 1)
+                    } else {
+                        (current + 1, node.offset)
+                    };
+                    stack[stack_size] = far;
+                    stack_size += 1;
+                    current = near;
+                    continue;
+                }
+            }
+            if stack_size == 0 {
+                break;
+            }
+            stack_size -= 1;
+            current = stack[stack_size];
+        }
+        hit_anything
+    }
+}
+
+// the whole tree lives in two flat arrays, wrap it in an Arc to use the same
+// tree from many threads and many renders at once
+#[derive(Clone)]
+pub struct BVHNode {
+    tree: BVHTree,
+    // use dyn to make explicit the dynamic dispatch of an object
+    primitives: Vec<Arc<dyn Hittable>>,
+    // the primitives without a bounding box (e.g. an infinite plane) cannot
+    // be placed in the tree, every ray tests them
+    unbounded: Vec<Arc<dyn Hittable>>,
+}
 
-        BVHNode {
-            left,
-            right,
-            bbox: aabb::surrounding_box(&left_bbox.unwrap(), &right_box.unwrap()),
+impl BVHNode {
+    pub fn from_list(mut hittable: Vec<Arc<dyn Hittable>>, time0: f64, time1: f64) -> Self {
+        Self::new(&mut hittable, time0, time1)
+    }
+
+    pub fn new(hittable: &mut [Arc<dyn Hittable>], time0: f64, time1: f64) -> Self {
+        let mut bounded = Vec::new();
+        let mut boxes = Vec::new();
+        let mut unbounded = Vec::new();
+        for h in hittable.iter() {
+            match h.bounding_box(time0, time1) {
+                Some(bbox) => {
+                    bounded.push(h.clone());
+                    boxes.push(bbox);
+                }
+                None => unbounded.push(h.clone()),
+            }
+        }
+        let (tree, order) = BVHTree::build(&boxes);
+        let primitives = order.iter().map(|&i| bounded[i].clone()).collect();
+        Self {
+            tree,
+            primitives,
+            unbounded,
         }
     }
+
+    pub fn stats(&self) -> BVHStats {
+        self.tree.stats()
+    }
 }
 
 impl Hittable for BVHNode {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        if self.bbox.hit(&ray, t_min, t_max) {
-            let left = self.left.hit(&ray, t_min, t_max);
-            let right = self.right.hit(&ray, t_min, t_max);
-            match (left, right) {
-                (Some(l), Some(r)) => {
-                    if l.t < r.t {
-                        Some(l)
-                    } else {
-                        Some(r)
-                    }
-                }
-                (Some(l), None) => Some(l),
-                (None, Some(r)) => Some(r),
-                _ => None,
+        // the unbounded primitives go first, a hit on them (e.g. the ground)
+        // shortens the ray that walks the tree
+        let mut closest_so_far = t_max;
+        let mut hit_anything: Option<HitRecord> = None;
+        for h in self.unbounded.iter() {
+            if let Some(hit) = h.hit(ray, t_min, closest_so_far) {
+                closest_so_far = hit.t;
+                hit_anything = Some(hit);
             }
+        }
+        self.tree
+            .hit(ray, t_min, closest_so_far, |i, closest_so_far| {
+                self.primitives[i].hit(ray, t_min, closest_so_far)
+            })
+            .or(hit_anything)
+    }
+
+    // there is no box around an unbounded primitive
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<AABB> {
+        if self.unbounded.is_empty() {
+            self.tree.bounding_box()
         } else {
             None
         }
     }
 
-    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<AABB> {
-        Some(self.bbox.clone())
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Vector3<f64>) -> f64 {
+        hittable::group_pdf_value(self, origin, direction)
+    }
+
+    fn random(&self, origin: &Vector3<f64>, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        hittable::group_random(self, origin, sampler)
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        for primitive in self.primitives.iter().chain(self.unbounded.iter()) {
+            primitive.collect_lights(lights);
+        }
     }
 }
@@ -1,10 +1,55 @@
 use nalgebra::Vector3;
-use rand::Rng;
 use std::f64;
 
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::util::random_in_unit_disk;
 
+// everything that describes a camera except the aspect ratio, which comes from
+// the size of the image, so a scene can be rendered at any resolution
+#[derive(Clone, Copy, Debug)]
+pub struct CameraSettings {
+    pub look_from: Vector3<f64>,
+    pub look_at: Vector3<f64>,
+    pub view_up: Vector3<f64>,
+    pub vertical_fov: f64,
+    pub aperture: f64,
+    pub focus_dist: f64,
+    pub time0: f64,
+    pub time1: f64,
+}
+
+impl CameraSettings {
+    pub fn build(&self, aspect: f64) -> Camera {
+        Camera::new(
+            self.look_from,
+            self.look_at,
+            self.view_up,
+            self.vertical_fov,
+            aspect,
+            self.aperture,
+            self.focus_dist,
+            self.time0,
+            self.time1,
+        )
+    }
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            look_from: Vector3::zeros(),
+            look_at: Vector3::new(0.0, 0.0, -1.0),
+            view_up: Vector3::new(0.0, 1.0, 0.0),
+            vertical_fov: 90.0,
+            aperture: 0.0,
+            focus_dist: 1.0,
+            time0: 0.0,
+            time1: 1.0,
+        }
+    }
+}
+
 pub struct Camera {
     origin: Vector3<f64>,
     lower_left_corner: Vector3<f64>,
@@ -50,15 +95,12 @@ impl Camera {
         }
     }
 
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-        let origin = if self.lens_radius == 0.0 {
-            self.origin
-        } else {
-            let rd = self.lens_radius * random_in_unit_disk();
-            let offset = self.u * rd.x + self.v * rd.y;
-            self.origin + offset
-        };
-        let time = self.time0 + rand::thread_rng().gen::<f64>() * (self.time1 - self.time0);
+    pub fn get_ray(&self, s: f64, t: f64, sampler: &mut dyn Sampler) -> Ray {
+        // the lens is sampled even when it is a pinhole, so the dimensions
+        // of the sampler that follow are the same for every camera
+        let rd = self.lens_radius * random_in_unit_disk(sampler);
+        let origin = self.origin + self.u * rd.x + self.v * rd.y;
+        let time = self.time0 + sampler.next_1d() * (self.time1 - self.time0);
         Ray::new(
             origin,
             self.lower_left_corner + s * self.horizontal + t * self.vertical - origin,
@@ -0,0 +1,140 @@
+use nalgebra::Vector3;
+use std::f64;
+
+use crate::aabb::AABB;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::util::{orthonormal_basis, quadratic_roots};
+
+/*
+* The points at radius from the segment between start and end: a cylinder
+* closed by two half spheres. u goes around the segment, v along the profile
+* from the pole of start to the pole of end, so the texture is not stretched
+* at the joints. Not sampled as a light
+*/
+pub struct Capsule<M: Material> {
+    start: Vector3<f64>,
+    end: Vector3<f64>,
+    radius: f64,
+    // the direction of the segment and two axes around it
+    axis: Vector3<f64>,
+    tangent: Vector3<f64>,
+    bitangent: Vector3<f64>,
+    length: f64,
+    material: M,
+}
+
+impl<M: Material> Capsule<M> {
+    // with start equal to end the capsule is a sphere
+    pub fn new(start: Vector3<f64>, end: Vector3<f64>, radius: f64, material: M) -> Self {
+        let length = (end - start).norm();
+        let axis = if length > 0.0 {
+            (end - start) / length
+        } else {
+            Vector3::new(0.0, 1.0, 0.0)
+        };
+        let (tangent, bitangent) = orthonormal_basis(&axis);
+        Self {
+            start,
+            end,
+            radius,
+            axis,
+            tangent,
+            bitangent,
+            length,
+            material,
+        }
+    }
+
+    // the position along the segment of the point p, 0 at start
+    fn height(&self, p: &Vector3<f64>) -> f64 {
+        (p - self.start).dot(&self.axis)
+    }
+
+    fn uv(&self, p: &Vector3<f64>, height: f64) -> (f64, f64) {
+        let d = p - self.start;
+        let phi = d.dot(&self.bitangent).atan2(d.dot(&self.tangent));
+        let u = (phi + f64::consts::PI) / (2.0 * f64::consts::PI);
+        // the length of the profile up to p
+        let quarter = 0.5 * f64::consts::PI * self.radius;
+        let elevation = |h: f64| (h / self.radius).clamp(-1.0, 1.0).asin() * self.radius;
+        let s = if height < 0.0 {
+            quarter + elevation(height)
+        } else if height > self.length {
+            quarter + self.length + elevation(height - self.length)
+        } else {
+            quarter + height
+        };
+        (u, s / (2.0 * quarter + self.length))
+    }
+}
+
+impl<M: Material> Hittable for Capsule<M> {
+    // the roots of the infinite cylinder around the segment and of the two
+    // spheres, each of them counts only on its own part of the capsule
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let direction = ray.direction();
+        let radius2 = self.radius * self.radius;
+        // every root with the heights along the segment where it counts
+        let mut roots = Vec::with_capacity(6);
+
+        let oc = ray.origin() - self.start;
+        let d = direction - direction.dot(&self.axis) * self.axis;
+        let o = oc - oc.dot(&self.axis) * self.axis;
+        if let Some((t0, t1)) = quadratic_roots(d.dot(&d), o.dot(&d), o.dot(&o) - radius2) {
+            for &t in [t0, t1].iter() {
+                roots.push((t, 0.0, self.length));
+            }
+        }
+        let caps = [
+            (self.start, f64::NEG_INFINITY, 0.0),
+            (self.end, self.length, f64::INFINITY),
+        ];
+        for &(center, low, high) in caps.iter() {
+            let oc = ray.origin() - center;
+            let a = direction.dot(&direction);
+            if let Some((t0, t1)) = quadratic_roots(a, oc.dot(&direction), oc.dot(&oc) - radius2) {
+                for &t in [t0, t1].iter() {
+                    roots.push((t, low, high));
+                }
+            }
+        }
+
+        let mut closest_so_far = t_max;
+        let mut hit_anything = None;
+        for &(t, low, high) in roots.iter() {
+            if t <= t_min || t >= closest_so_far {
+                continue;
+            }
+            let p = ray.pointing_at(t);
+            let height = self.height(&p);
+            if height >= low && height <= high {
+                closest_so_far = t;
+                hit_anything = Some((t, p, height));
+            }
+        }
+
+        hit_anything.map(|(t, p, height)| {
+            let nearest = self.start + height.max(0.0).min(self.length) * self.axis;
+            let (u, v) = self.uv(&p, height);
+            HitRecord {
+                t,
+                u,
+                v,
+                p,
+                normal: (p - nearest) / self.radius,
+                material: &self.material,
+                vertex_color: None,
+            }
+        })
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<AABB> {
+        let radius = Vector3::repeat(self.radius);
+        Some(AABB::new(
+            self.start.inf(&self.end) - radius,
+            self.start.sup(&self.end) + radius,
+        ))
+    }
+}
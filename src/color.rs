@@ -1,23 +1,187 @@
 use nalgebra::Vector3;
 
-use crate::hittable::Hittable;
+use crate::background::Background;
+use crate::hittable::{self, Hittable};
+use crate::material::Lobe;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 
-pub fn color(ray: &Ray, world: &Box<dyn Hittable>, depth: usize) -> Vector3<f64> {
-    if let Some(hit) = world.hit(ray, 0.001, f64::MAX) {
-        let emitted = hit.material.emitted(hit.u, hit.v, &hit.p);
-        if depth < 50 {
-            if let Some((scattered, attenuation)) = hit.material.scatter(&ray, &hit) {
-                return emitted
-                    + attenuation.zip_map(&color(&scattered, &world, depth + 1), |l, r| l * r);
+/*
+* Path tracing with next-event estimation: at every diffuse bounce a shadow
+* ray goes toward a point sampled on the lights, besides the ray scattered by
+* the material. A light can be reached by both, so the two estimates are
+* combined with multiple importance sampling (power heuristic)
+*/
+
+// the bounces a path is allowed to do. max is the total, every kind of lobe
+// can have a lower limit of its own. after min bounces a path survives with
+// a probability that follows its throughput (russian roulette), the paths
+// that survive carry more weight so the average does not change
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PathDepth {
+    pub max: usize,
+    pub min: usize,
+    pub diffuse: usize,
+    pub specular: usize,
+    pub transmission: usize,
+    pub volume: usize,
+}
+
+impl PathDepth {
+    // max bounces of any kind, without russian roulette
+    pub fn new(max: usize) -> Self {
+        Self {
+            max,
+            min: max,
+            diffuse: usize::MAX,
+            specular: usize::MAX,
+            transmission: usize::MAX,
+            volume: usize::MAX,
+        }
+    }
+
+    fn limit(&self, lobe: Lobe) -> usize {
+        match lobe {
+            Lobe::Diffuse => self.diffuse,
+            Lobe::Specular => self.specular,
+            Lobe::Transmission => self.transmission,
+            Lobe::Volume => self.volume,
+        }
+    }
+}
+
+impl Default for PathDepth {
+    fn default() -> Self {
+        Self {
+            min: 3,
+            ..Self::new(50)
+        }
+    }
+}
+
+// what the next-event estimation samples: the emissive hittables and the
+// background, when it can be sampled. every light is picked with the same
+// probability
+struct Lights<'a> {
+    hittables: &'a [&'a dyn Hittable],
+    background: Option<&'a dyn Background>,
+}
+
+impl<'a> Lights<'a> {
+    fn count(&self) -> usize {
+        self.hittables.len() + self.background.is_some() as usize
+    }
+
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Vector3<f64>) -> f64 {
+        let count = self.count();
+        if count == 0 {
+            return 0.0;
+        }
+        let hittables = hittable::lights_pdf_value(self.hittables, origin, direction)
+            * self.hittables.len() as f64;
+        let background = self.background.map_or(0.0, |b| b.pdf_value(direction));
+        (hittables + background) / count as f64
+    }
+
+    fn random(&self, origin: &Vector3<f64>, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        let count = self.count();
+        let i = ((sampler.next_1d() * count as f64) as usize).min(count - 1);
+        match self.background {
+            Some(background) if i == self.hittables.len() => background.random(sampler),
+            _ => self.hittables[i].random(origin, sampler),
+        }
+    }
+}
+
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let pdf2 = pdf * pdf;
+    pdf2 / (pdf2 + other_pdf * other_pdf)
+}
+
+// lights are the hittables collected by Hittable::collect_lights, they are
+// sampled together with the background if Background::is_light. with no
+// lights only the material sampling is used. every random number of the path
+// comes from sampler
+pub fn color(
+    ray: &Ray,
+    world: &dyn Hittable,
+    lights: &[&dyn Hittable],
+    background: &dyn Background,
+    depth: &PathDepth,
+    sampler: &mut dyn Sampler,
+) -> Vector3<f64> {
+    let lights = Lights {
+        hittables: lights,
+        background: Some(background).filter(|b| b.is_light()),
+    };
+    let mut radiance = Vector3::zeros();
+    let mut throughput = Vector3::new(1.0, 1.0, 1.0);
+    let mut ray = *ray;
+    // the density the material sampled the ray with, None when the ray
+    // does not come from a bounce that also sampled the lights
+    let mut bsdf_pdf: Option<f64> = None;
+    // bounces done by each kind of lobe, in the order of Lobe
+    let mut lobe_bounces = [0; 4];
+
+    for bounce in 0.. {
+        let hit = world.hit(&ray, 0.001, f64::MAX);
+        let mut emitted = match &hit {
+            Some(hit) => hit.material.emitted(hit.u, hit.v, &hit.p),
+            None => background.radiance(&ray),
+        };
+        if let Some(bsdf_pdf) = bsdf_pdf {
+            let light_pdf = lights.pdf_value(&ray.origin(), &ray.direction());
+            emitted *= power_heuristic(bsdf_pdf, light_pdf);
+        }
+        radiance += throughput.component_mul(&emitted);
+        let hit = match hit {
+            Some(hit) => hit,
+            None => break,
+        };
+        if bounce == depth.max {
+            break;
+        }
+
+        let wo = -ray.direction().normalize();
+        let sample = match hit.material.sample(&hit, &wo, sampler) {
+            Some(sample) if sample.pdf > 0.0 => sample,
+            _ => break,
+        };
+        lobe_bounces[sample.lobe as usize] += 1;
+        if lobe_bounces[sample.lobe as usize] > depth.limit(sample.lobe) {
+            break;
+        }
+
+        // a delta lobe cannot be evaluated toward a light, the lights are
+        // only found by the scattered ray and keep all their contribution
+        bsdf_pdf = None;
+        if !sample.delta && lights.count() > 0 {
+            bsdf_pdf = Some(sample.pdf);
+            let wi = lights.random(&hit.p, sampler).normalize();
+            let light_pdf = lights.pdf_value(&hit.p, &wi);
+            let pdf = hit.material.pdf(&hit, &wo, &wi);
+            if light_pdf > 0.0 && pdf > 0.0 {
+                let shadow = Ray::new(hit.p, wi, ray.time());
+                let le = match world.hit(&shadow, 0.001, f64::MAX) {
+                    Some(light) => light.material.emitted(light.u, light.v, &light.p),
+                    None => background.radiance(&shadow),
+                };
+                let f = hit.material.eval(&hit, &wo, &wi);
+                let weight = power_heuristic(light_pdf, pdf) / light_pdf;
+                radiance += throughput.component_mul(&f.component_mul(&le)) * weight;
+            }
+        }
+
+        throughput.component_mul_assign(&(sample.f / sample.pdf));
+        ray = Ray::new(hit.p, sample.wi, ray.time());
+
+        if bounce >= depth.min {
+            let survival = throughput.max().min(1.0);
+            if survival <= 0.0 || sampler.next_1d() >= survival {
+                break;
             }
+            throughput /= survival;
         }
-        // Vector3::new(0.0, 0.0, 0.0)
-        emitted
-    } else {
-        // let unit_direction = ray.direction().normalize();
-        // let t = 0.5 * (unit_direction.y + 1.0);
-        // (1.0 - t) * Vector3::new(1.0, 1.0, 1.0) + t * Vector3::new(0.5, 0.7, 1.0)
-        Vector3::new(0.0, 0.0, 0.0)
     }
+    radiance
 }
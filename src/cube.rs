@@ -3,7 +3,9 @@ use crate::hittable::{HitRecord, Hittable, HittableList};
 use crate::material::Material;
 use crate::ray::Ray;
 use crate::rect::{Plane, Rect};
+use crate::sampler::Sampler;
 use nalgebra::Vector3;
+use std::sync::Arc;
 
 pub struct Cube {
     p_min: Vector3<f64>,
@@ -12,11 +14,13 @@ pub struct Cube {
 }
 
 impl Cube {
-    pub fn new<M: Material + Clone + 'static>(
+    // the sides share the material
+    pub fn new<M: Material + 'static>(
         p_min: Vector3<f64>,
         p_max: Vector3<f64>,
         material: M,
     ) -> Self {
+        let material = Arc::new(material);
         let mut sides = HittableList::default();
         sides.push(Rect::new(
             Plane::XY,
@@ -91,4 +95,16 @@ impl Hittable for Cube {
             max: self.p_max,
         })
     }
+
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Vector3<f64>) -> f64 {
+        self.sides.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Vector3<f64>, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        self.sides.random(origin, sampler)
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        self.sides.collect_lights(lights)
+    }
 }
@@ -0,0 +1,109 @@
+use nalgebra::Vector3;
+use std::f64;
+
+use crate::aabb::AABB;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::util::{orthonormal_basis, random_in_unit_disk};
+
+/*
+* A flat disk facing the direction of its normal. The uv of a hit are polar:
+* u is the angle around the normal divided by 2 pi, v the distance from the
+* center divided by the radius
+*/
+pub struct Disk<M: Material> {
+    center: Vector3<f64>,
+    normal: Vector3<f64>,
+    radius: f64,
+    // two axes on the disk, u is the angle 0
+    tangent: Vector3<f64>,
+    bitangent: Vector3<f64>,
+    material: M,
+}
+
+impl<M: Material> Disk<M> {
+    pub fn new(center: Vector3<f64>, normal: Vector3<f64>, radius: f64, material: M) -> Self {
+        let normal = normal.normalize();
+        let (tangent, bitangent) = orthonormal_basis(&normal);
+        Self {
+            center,
+            normal,
+            radius,
+            tangent,
+            bitangent,
+            material,
+        }
+    }
+
+    pub fn area(&self) -> f64 {
+        f64::consts::PI * self.radius * self.radius
+    }
+}
+
+impl<M: Material> Hittable for Disk<M> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let denominator = self.normal.dot(&ray.direction());
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let t = self.normal.dot(&(self.center - ray.origin())) / denominator;
+        if t < t_min || t > t_max {
+            return None;
+        }
+        let p = ray.pointing_at(t);
+        let d = p - self.center;
+        let distance2 = d.magnitude_squared();
+        if distance2 > self.radius * self.radius {
+            return None;
+        }
+        let phi = d.dot(&self.bitangent).atan2(d.dot(&self.tangent));
+        let u = if phi < 0.0 {
+            phi + 2.0 * f64::consts::PI
+        } else {
+            phi
+        } / (2.0 * f64::consts::PI);
+        Some(HitRecord {
+            t,
+            u,
+            v: distance2.sqrt() / self.radius,
+            p,
+            normal: self.normal,
+            material: &self.material,
+            vertex_color: None,
+        })
+    }
+
+    // along an axis the disk reaches radius * sin of the angle between the
+    // axis and the normal
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<AABB> {
+        let extent = self
+            .normal
+            .map(|n| self.radius * (1.0 - n * n).max(0.0).sqrt() + 0.0001);
+        Some(AABB::new(self.center - extent, self.center + extent))
+    }
+
+    // uniform on the area, like the quads
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Vector3<f64>) -> f64 {
+        match self.hit(&Ray::new(*origin, *direction, 0.0), 0.001, f64::MAX) {
+            Some(hit) => {
+                let distance2 = hit.t * hit.t * direction.magnitude_squared();
+                let cosine = (self.normal.dot(direction) / direction.magnitude()).abs();
+                distance2 / (cosine * self.area())
+            }
+            None => 0.0,
+        }
+    }
+
+    fn random(&self, origin: &Vector3<f64>, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        let p = random_in_unit_disk(sampler) * self.radius;
+        self.center + p.x * self.tangent + p.y * self.bitangent - origin
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        if self.material.is_emissive() {
+            lights.push(self);
+        }
+    }
+}
@@ -1,8 +1,10 @@
 use nalgebra::Vector3;
+use std::sync::Arc;
 
 use crate::aabb::{self, AABB};
 use crate::material::Material;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 
 // 'a is a lifetime parameter. is used to indicate that the struct
 // can contain references with a specific lifetime
@@ -13,11 +15,129 @@ pub struct HitRecord<'a> {
     pub p: Vector3<f64>,
     pub normal: Vector3<f64>,
     pub material: &'a dyn Material,
+    // color interpolated from the vertices of a mesh, if it has any
+    pub vertex_color: Option<Vector3<f64>>,
 }
 
-pub trait Hittable {
+pub trait Hittable: Send + Sync {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
     fn bounding_box(&self, t0: f64, t1: f64) -> Option<AABB>;
+
+    // light sampling, implemented by the hittables that can be lights.
+    // the density over the solid angle of random() returning direction
+    fn pdf_value(&self, _origin: &Vector3<f64>, _direction: &Vector3<f64>) -> f64 {
+        0.0
+    }
+
+    // a random direction from origin toward the hittable
+    fn random(&self, _origin: &Vector3<f64>, _sampler: &mut dyn Sampler) -> Vector3<f64> {
+        Vector3::new(1.0, 0.0, 0.0)
+    }
+
+    // adds the parts made of an emissive material to lights. a wrapper adds
+    // itself, so the lights are sampled with its transformation
+    fn collect_lights<'a>(&'a self, _lights: &mut Vec<&'a dyn Hittable>) {}
+}
+
+// every light of the list is picked with the same probability
+pub fn lights_pdf_value(
+    lights: &[&dyn Hittable],
+    origin: &Vector3<f64>,
+    direction: &Vector3<f64>,
+) -> f64 {
+    if lights.is_empty() {
+        return 0.0;
+    }
+    let sum: f64 = lights.iter().map(|l| l.pdf_value(origin, direction)).sum();
+    sum / lights.len() as f64
+}
+
+pub fn lights_random(
+    lights: &[&dyn Hittable],
+    origin: &Vector3<f64>,
+    sampler: &mut dyn Sampler,
+) -> Vector3<f64> {
+    let i = ((sampler.next_1d() * lights.len() as f64) as usize).min(lights.len() - 1);
+    lights[i].random(origin, sampler)
+}
+
+// a wrapper that holds any light is a light itself
+pub(crate) fn has_lights(hittable: &dyn Hittable) -> bool {
+    let mut lights = Vec::new();
+    hittable.collect_lights(&mut lights);
+    !lights.is_empty()
+}
+
+// the light sampling of a group of hittables, used when the group is inside
+// a wrapper: the lights of the group are sampled like a list of lights
+pub(crate) fn group_pdf_value(
+    group: &dyn Hittable,
+    origin: &Vector3<f64>,
+    direction: &Vector3<f64>,
+) -> f64 {
+    let mut lights = Vec::new();
+    group.collect_lights(&mut lights);
+    lights_pdf_value(&lights, origin, direction)
+}
+
+pub(crate) fn group_random(
+    group: &dyn Hittable,
+    origin: &Vector3<f64>,
+    sampler: &mut dyn Sampler,
+) -> Vector3<f64> {
+    let mut lights = Vec::new();
+    group.collect_lights(&mut lights);
+    if lights.is_empty() {
+        Vector3::new(1.0, 0.0, 0.0)
+    } else {
+        lights_random(&lights, origin, sampler)
+    }
+}
+
+// a shared hittable (e.g. a BVH built once) can be used everywhere an owned one is
+// expected, every clone of the Arc points to the same acceleration structure
+impl<H: Hittable + ?Sized> Hittable for Arc<H> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        (**self).hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<AABB> {
+        (**self).bounding_box(t0, t1)
+    }
+
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Vector3<f64>) -> f64 {
+        (**self).pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Vector3<f64>, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        (**self).random(origin, sampler)
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        (**self).collect_lights(lights)
+    }
+}
+
+impl<H: Hittable + ?Sized> Hittable for Box<H> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        (**self).hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<AABB> {
+        (**self).bounding_box(t0, t1)
+    }
+
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Vector3<f64>) -> f64 {
+        (**self).pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Vector3<f64>, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        (**self).random(origin, sampler)
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        (**self).collect_lights(lights)
+    }
 }
 
 #[derive(Default)]
@@ -62,6 +182,20 @@ impl Hittable for HittableList {
             _ => None,
         }
     }
+
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Vector3<f64>) -> f64 {
+        group_pdf_value(self, origin, direction)
+    }
+
+    fn random(&self, origin: &Vector3<f64>, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        group_random(self, origin, sampler)
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        for h in self.list.iter() {
+            h.collect_lights(lights);
+        }
+    }
 }
 
 pub struct FlipNormals<H: Hittable> {
@@ -85,4 +219,18 @@ impl<H: Hittable> Hittable for FlipNormals<H> {
     fn bounding_box(&self, t0: f64, t1: f64) -> Option<AABB> {
         self.hittable.bounding_box(t0, t1)
     }
+
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Vector3<f64>) -> f64 {
+        self.hittable.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Vector3<f64>, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        self.hittable.random(origin, sampler)
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        if has_lights(&self.hittable) {
+            lights.push(self);
+        }
+    }
 }
@@ -0,0 +1,84 @@
+use nalgebra::{Matrix4, Vector3};
+use std::sync::Arc;
+
+use crate::aabb::AABB;
+use crate::hittable::{self, HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::transform::Transform;
+
+/*
+* A copy of a shared prototype (a mesh, a BVH of primitives, ...) somewhere
+* else in the scene. The instances only hold a transform and a pointer to the
+* prototype, so a forest of identical trees stores the tree once. An instance
+* can paint the prototype with a material of its own, that material cannot be
+* emissive: a mesh or a BVH has no way to sample its surface, so it would be
+* a light found only by the rays that hit it. The lights of a painted
+* prototype are not sampled either, they are painted over
+*/
+pub struct Instance {
+    shape: Transform<Arc<dyn Hittable>>,
+    material: Option<Arc<dyn Material>>,
+}
+
+impl Instance {
+    pub fn new(prototype: Arc<dyn Hittable>, matrix: Matrix4<f64>) -> Self {
+        Self {
+            shape: Transform::new(prototype, matrix),
+            material: None,
+        }
+    }
+
+    // every hit of the instance gets material instead of the one of the
+    // prototype. the lights go in the prototype
+    pub fn with_material(
+        prototype: Arc<dyn Hittable>,
+        matrix: Matrix4<f64>,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        assert!(
+            !material.is_emissive(),
+            "the material of an instance cannot be emissive"
+        );
+        Self {
+            shape: Transform::new(prototype, matrix),
+            material: Some(material),
+        }
+    }
+
+    pub fn matrix(&self) -> &Matrix4<f64> {
+        self.shape.matrix()
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let hit = self.shape.hit(ray, t_min, t_max);
+        match &self.material {
+            Some(material) => hit.map(|hit| HitRecord {
+                material: material.as_ref(),
+                ..hit
+            }),
+            None => hit,
+        }
+    }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<AABB> {
+        self.shape.bounding_box(t0, t1)
+    }
+
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Vector3<f64>) -> f64 {
+        self.shape.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Vector3<f64>, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        self.shape.random(origin, sampler)
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        if self.material.is_none() && hittable::has_lights(&self.shape) {
+            lights.push(self);
+        }
+    }
+}
@@ -1,18 +1,37 @@
 pub mod aabb;
+pub mod background;
 pub mod bvh;
 pub mod camera;
+pub mod capsule;
 pub mod color;
 pub mod cube;
+pub mod disk;
 pub mod hittable;
+pub mod instance;
 pub mod logger;
 pub mod material;
 pub mod medium;
+pub mod mesh;
+pub mod obj;
+pub mod output;
 pub mod perlin;
+pub mod plane;
+pub mod ply;
 pub mod progressbar;
+pub mod quad;
+pub mod quadric;
 pub mod ray;
 pub mod rect;
+pub mod renderer;
 pub mod rotate;
+pub mod sampler;
+pub mod scene;
+pub mod scenes;
 pub mod sphere;
 pub mod texture;
+pub mod tonemap;
+pub mod torus;
+pub mod transform;
 pub mod traslate;
+pub mod triangle;
 pub mod util;
@@ -0,0 +1,272 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use raytracing_in_rust::output::{self, OutputFormat};
+use raytracing_in_rust::progressbar::ProgressBar;
+use raytracing_in_rust::sampler::SamplerType;
+use raytracing_in_rust::scene::{self, Scene};
+use raytracing_in_rust::scenes;
+use raytracing_in_rust::tonemap::ToneMapOperator;
+
+const USAGE: &str = "usage: raytracing-in-rust [options] <scene file or built-in scene name>
+
+options:
+    -o, --output <path>    image to write (default: image.png)
+    -f, --format <format>  image format, by default from the output extension:
+                           ppm, png, png16 (16 bit png), exr or hdr
+    -w, --width <pixels>   image width, the height keeps the aspect ratio if not given
+    -H, --height <pixels>  image height
+    -s, --spp <samples>    samples per pixel
+    -d, --depth <bounces>  maximum number of bounces of a path
+        --min-depth <bounces>
+                           bounces before the paths can be ended at random
+                           by russian roulette
+    -t, --threads <count>  render threads (default: one per core)
+        --seed <number>    seed of every random number, renders with the same
+                           seed are identical
+        --sampler <name>   the random numbers of the samples: independent,
+                           stratified, halton or sobol (default)
+        --tonemap <name>   tone mapping of the 8 and 16 bit formats: clamp, reinhard,
+                           extended_reinhard, aces or agx
+        --exposure <stops> exposure compensation before the tone mapping
+        --white <radiance> white point of extended_reinhard
+    -q, --quiet            no progress bar
+    -l, --list             print the names of the built-in scenes
+    -h, --help             print this message";
+
+// exit codes: 0 when the image is written, 1 when the render fails and
+// 2 when the command line is wrong
+const EXIT_FAILURE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+#[derive(Default)]
+struct Options {
+    scene: Option<String>,
+    output: Option<PathBuf>,
+    format: Option<String>,
+    width: Option<usize>,
+    height: Option<usize>,
+    samples: Option<usize>,
+    max_depth: Option<usize>,
+    min_depth: Option<usize>,
+    threads: Option<usize>,
+    seed: Option<u64>,
+    sampler: Option<SamplerType>,
+    tonemap: Option<ToneMapOperator>,
+    exposure: Option<f64>,
+    white: Option<f64>,
+    quiet: bool,
+    list: bool,
+    help: bool,
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value
+        .parse::<T>()
+        .map_err(|_| format!("'{}' is not a valid value for {}", value, flag))
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => {
+                let value = args.next().ok_or("--output needs a value")?;
+                options.output = Some(PathBuf::from(value));
+            }
+            "-f" | "--format" => {
+                options.format = Some(args.next().ok_or("--format needs a value")?);
+            }
+            "-w" | "--width" => options.width = Some(parse_number(&arg, args.next())?),
+            "-H" | "--height" => options.height = Some(parse_number(&arg, args.next())?),
+            "-s" | "--spp" => options.samples = Some(parse_number(&arg, args.next())?),
+            "-d" | "--depth" => options.max_depth = Some(parse_number(&arg, args.next())?),
+            "--min-depth" => options.min_depth = Some(parse_number(&arg, args.next())?),
+            "-t" | "--threads" => options.threads = Some(parse_number(&arg, args.next())?),
+            "--seed" => options.seed = Some(parse_number(&arg, args.next())?),
+            "--sampler" => {
+                let name = args.next().ok_or("--sampler needs a value")?;
+                let sampler = SamplerType::from_name(&name).ok_or_else(|| {
+                    format!(
+                        "unknown sampler '{}', use {}",
+                        name,
+                        SamplerType::NAMES.join(", ")
+                    )
+                })?;
+                options.sampler = Some(sampler);
+            }
+            "--tonemap" => {
+                let name = args.next().ok_or("--tonemap needs a value")?;
+                let operator = ToneMapOperator::from_name(&name).ok_or_else(|| {
+                    format!(
+                        "unknown tone mapping '{}', use {}",
+                        name,
+                        ToneMapOperator::NAMES.join(", ")
+                    )
+                })?;
+                options.tonemap = Some(operator);
+            }
+            "--exposure" => options.exposure = Some(parse_number(&arg, args.next())?),
+            "--white" => options.white = Some(parse_number(&arg, args.next())?),
+            "-q" | "--quiet" => options.quiet = true,
+            "-l" | "--list" => options.list = true,
+            "-h" | "--help" => options.help = true,
+            _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+            _ if options.scene.is_some() => return Err("only one scene can be rendered".into()),
+            _ => options.scene = Some(arg),
+        }
+    }
+    for (flag, value) in [
+        ("--width", options.width),
+        ("--height", options.height),
+        ("--spp", options.samples),
+        ("--threads", options.threads),
+    ] {
+        if value == Some(0) {
+            return Err(format!("{} must be greater than 0", flag));
+        }
+    }
+    Ok(options)
+}
+
+// the flags win over the settings of the scene
+fn apply(options: &Options, scene: &mut Scene) {
+    let settings = &mut scene.settings;
+    let aspect = settings.aspect_ratio();
+    match (options.width, options.height) {
+        (Some(width), Some(height)) => {
+            settings.width = width;
+            settings.height = height;
+        }
+        (Some(width), None) => {
+            settings.width = width;
+            settings.height = ((width as f64 / aspect).round() as usize).max(1);
+        }
+        (None, Some(height)) => {
+            settings.height = height;
+            settings.width = ((height as f64 * aspect).round() as usize).max(1);
+        }
+        (None, None) => {}
+    }
+    if let Some(samples) = options.samples {
+        settings.samples = samples;
+    }
+    if let Some(max_depth) = options.max_depth {
+        settings.depth.max = max_depth;
+    }
+    if let Some(min_depth) = options.min_depth {
+        settings.depth.min = min_depth;
+    }
+    if options.seed.is_some() {
+        settings.seed = options.seed;
+    }
+    if let Some(sampler) = options.sampler {
+        settings.sampler = sampler;
+    }
+    if let Some(operator) = options.tonemap {
+        settings.tone_mapping.operator = operator;
+    }
+    if let Some(exposure) = options.exposure {
+        settings.tone_mapping.exposure = exposure;
+    }
+    if let Some(white) = options.white {
+        settings.tone_mapping.white = white;
+    }
+}
+
+// the image file and its format, a format that does not exist is a mistake
+// of the command line like a wrong flag
+fn output_format(options: &Options) -> Result<(PathBuf, OutputFormat), String> {
+    let output = options
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("image.png"));
+    let format = match &options.format {
+        Some(name) => OutputFormat::from_name(name)
+            .ok_or_else(|| format!("unknown image format '{}'", name))?,
+        None => OutputFormat::from_path(&output)
+            .ok_or_else(|| format!("unknown image format for {}", output.display()))?,
+    };
+    Ok((output, format))
+}
+
+fn run(options: &Options, output: &Path, format: OutputFormat) -> Result<(), String> {
+    if let Some(threads) = options.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .map_err(|e| e.to_string())?;
+    }
+
+    let path = options.scene.as_ref().ok_or("no scene to render")?;
+    // a file wins over a built-in scene with the same name
+    let mut scene = if Path::new(path).exists() {
+        scene::load(path).map_err(|e| e.to_string())?
+    } else {
+        scenes::by_name(path).ok_or_else(|| {
+            format!(
+                "'{}' is neither a scene file nor a built-in scene ({})",
+                path,
+                scenes::NAMES.join(", ")
+            )
+        })?
+    };
+    apply(options, &mut scene);
+
+    let settings = scene.settings;
+    let renderer = scene.renderer();
+    let camera = scene.camera();
+    let frame = if options.quiet {
+        renderer.render(&camera, scene.world.as_ref())
+    } else {
+        let description = format!(
+            "{} {}x{} {} spp",
+            path, settings.width, settings.height, settings.samples
+        );
+        let bar = ProgressBar::new(settings.height, &description);
+        let frame =
+            renderer.render_with_progress(&camera, scene.world.as_ref(), |rows| bar.set(rows));
+        bar.finish();
+        frame
+    };
+
+    output::save(&frame, output, format, &settings.tone_mapping)
+        .map_err(|e| format!("cannot write {}: {}", output.display(), e))
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            process::exit(EXIT_USAGE);
+        }
+    };
+    if options.help {
+        println!("{}", USAGE);
+        return;
+    }
+    if options.list {
+        for name in scenes::NAMES.iter() {
+            println!("{}", name);
+        }
+        return;
+    }
+    if options.scene.is_none() {
+        eprintln!("error: no scene to render\n\n{}", USAGE);
+        process::exit(EXIT_USAGE);
+    }
+    let (output, format) = match output_format(&options) {
+        Ok(output) => output,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            process::exit(EXIT_USAGE);
+        }
+    };
+    if let Err(message) = run(&options, &output, format) {
+        eprintln!("error: {}", message);
+        process::exit(EXIT_FAILURE);
+    }
+}
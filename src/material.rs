@@ -1,10 +1,12 @@
 use nalgebra::Vector3;
-use rand::Rng;
+use std::f64;
+use std::sync::Arc;
 
 use crate::hittable::HitRecord;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::texture::Texture;
-use crate::util::random_in_unit_sphere;
+use crate::util::{random_in_unit_sphere, random_unit_vector};
 
 fn reflect(v: &Vector3<f64>, n: &Vector3<f64>) -> Vector3<f64> {
     v - 2.0 * v.dot(&n) * n
@@ -27,9 +29,113 @@ fn schlick(cosine: f64, ref_idx: f64) -> f64 {
     r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
 }
 
-pub trait Material: Sync {
-    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<(Ray, Vector3<f64>)>;
+// the kind of scattering of a sample, a path can limit the bounces of each
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lobe {
+    Diffuse,
+    // reflection, mirror-like or glossy
+    Specular,
+    // refraction through the surface
+    Transmission,
+    // scattering inside a medium
+    Volume,
+}
+
+// a direction picked by Material::sample. f is the bsdf times the cosine
+// between wi and the normal (the phase function for the media), so a path
+// carries f / pdf. a delta lobe, like a mirror, cannot be evaluated or hit
+// by a light sample: its pdf is 1 and f is the weight of the lobe
+#[derive(Clone, Copy, Debug)]
+pub struct BsdfSample {
+    pub wi: Vector3<f64>,
+    pub f: Vector3<f64>,
+    pub pdf: f64,
+    pub delta: bool,
+    pub lobe: Lobe,
+}
+
+// wo points from the hit toward where the ray came from and wi toward where
+// the light comes from, both normalized
+pub trait Material: Send + Sync {
+    fn sample(
+        &self,
+        hit: &HitRecord,
+        wo: &Vector3<f64>,
+        sampler: &mut dyn Sampler,
+    ) -> Option<BsdfSample>;
     fn emitted(&self, u: f64, v: f64, p: &Vector3<f64>) -> Vector3<f64>;
+
+    // f of sample() for a given pair of directions, 0 for the delta lobes
+    fn eval(&self, _hit: &HitRecord, _wo: &Vector3<f64>, _wi: &Vector3<f64>) -> Vector3<f64> {
+        Vector3::zeros()
+    }
+
+    // density over the solid angle of sample() picking wi, 0 for the delta
+    // lobes
+    fn pdf(&self, _hit: &HitRecord, _wo: &Vector3<f64>, _wi: &Vector3<f64>) -> f64 {
+        0.0
+    }
+
+    // the hittables made of an emissive material are sampled as lights
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    // the scattered ray and the attenuation of the path
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Vector3<f64>)> {
+        let wo = -ray.direction().normalize();
+        self.sample(hit, &wo, sampler)
+            .filter(|sample| sample.pdf > 0.0)
+            .map(|sample| {
+                (
+                    Ray::new(hit.p, sample.wi, ray.time()),
+                    sample.f / sample.pdf,
+                )
+            })
+    }
+}
+
+// lets objects share one material, or use a material chosen at runtime
+// (e.g. Arc<dyn Material> read from a file)
+impl<M: Material + ?Sized> Material for Arc<M> {
+    fn sample(
+        &self,
+        hit: &HitRecord,
+        wo: &Vector3<f64>,
+        sampler: &mut dyn Sampler,
+    ) -> Option<BsdfSample> {
+        (**self).sample(hit, wo, sampler)
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Vector3<f64>) -> Vector3<f64> {
+        (**self).emitted(u, v, p)
+    }
+
+    fn eval(&self, hit: &HitRecord, wo: &Vector3<f64>, wi: &Vector3<f64>) -> Vector3<f64> {
+        (**self).eval(hit, wo, wi)
+    }
+
+    fn pdf(&self, hit: &HitRecord, wo: &Vector3<f64>, wi: &Vector3<f64>) -> f64 {
+        (**self).pdf(hit, wo, wi)
+    }
+
+    fn is_emissive(&self) -> bool {
+        (**self).is_emissive()
+    }
+
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Vector3<f64>)> {
+        (**self).scatter(ray, hit, sampler)
+    }
 }
 
 #[derive(Clone)]
@@ -37,7 +143,7 @@ pub struct Lambertian<T: Texture> {
     albedo: T,
 }
 
-impl<T: Texture + std::marker::Sync> Lambertian<T> {
+impl<T: Texture> Lambertian<T> {
     pub fn new(albedo: T) -> Self {
         Self { albedo }
     }
@@ -45,16 +151,46 @@ impl<T: Texture + std::marker::Sync> Lambertian<T> {
 
 // '_' before a variable name tells the compiler to not worry if the
 // parameter is not used. unless it throw a warning
-impl<T: Texture + std::marker::Sync> Material for Lambertian<T> {
-    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<(Ray, Vector3<f64>)> {
-        let target = hit.p + hit.normal + random_in_unit_sphere();
-        let scattered = Ray::new(hit.p, target - hit.p, ray.time());
-        Some((scattered, self.albedo.value(hit.u, hit.v, &hit.p)))
+impl<T: Texture> Material for Lambertian<T> {
+    // a point on the unit sphere around the tip of the normal gives directions
+    // with a density proportional to the cosine with the normal
+    fn sample(
+        &self,
+        hit: &HitRecord,
+        wo: &Vector3<f64>,
+        sampler: &mut dyn Sampler,
+    ) -> Option<BsdfSample> {
+        let direction = hit.normal + random_unit_vector(sampler);
+        let wi = if direction.magnitude_squared() < 1e-12 {
+            hit.normal
+        } else {
+            direction.normalize()
+        };
+        Some(BsdfSample {
+            wi,
+            f: self.eval(hit, wo, &wi),
+            pdf: self.pdf(hit, wo, &wi),
+            delta: false,
+            lobe: Lobe::Diffuse,
+        })
     }
 
     fn emitted(&self, _u: f64, _v: f64, _p: &Vector3<f64>) -> Vector3<f64> {
         Vector3::zeros()
     }
+
+    fn eval(&self, hit: &HitRecord, wo: &Vector3<f64>, wi: &Vector3<f64>) -> Vector3<f64> {
+        self.albedo.value_at(hit) * self.pdf(hit, wo, wi)
+    }
+
+    fn pdf(&self, hit: &HitRecord, _wo: &Vector3<f64>, wi: &Vector3<f64>) -> f64 {
+        let cosine = hit.normal.dot(wi);
+        if cosine > 0.0 {
+            cosine / f64::consts::PI
+        } else {
+            0.0
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -63,7 +199,7 @@ pub struct Metal<T: Texture> {
     fuzz: f64,
 }
 
-impl<T: Texture + std::marker::Sync> Metal<T> {
+impl<T: Texture> Metal<T> {
     pub fn new(albedo: T, fuzz: f64) -> Self {
         Self {
             albedo,
@@ -72,15 +208,26 @@ impl<T: Texture + std::marker::Sync> Metal<T> {
     }
 }
 
-impl<T: Texture + std::marker::Sync> Material for Metal<T> {
-    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<(Ray, Vector3<f64>)> {
-        let mut reflected = reflect(&ray.direction().normalize(), &hit.normal);
+// the fuzzy reflection has no density to evaluate, so it is a delta lobe too
+impl<T: Texture> Material for Metal<T> {
+    fn sample(
+        &self,
+        hit: &HitRecord,
+        wo: &Vector3<f64>,
+        sampler: &mut dyn Sampler,
+    ) -> Option<BsdfSample> {
+        let mut reflected = reflect(&-wo, &hit.normal);
         if self.fuzz > 0.0 {
-            reflected += self.fuzz * random_in_unit_sphere()
+            reflected += self.fuzz * random_in_unit_sphere(sampler)
         };
         if reflected.dot(&hit.normal) > 0.0 {
-            let scattered = Ray::new(hit.p, reflected, ray.time());
-            Some((scattered, self.albedo.value(hit.u, hit.v, &hit.p)))
+            Some(BsdfSample {
+                wi: reflected.normalize(),
+                f: self.albedo.value_at(hit),
+                pdf: 1.0,
+                delta: true,
+                lobe: Lobe::Specular,
+            })
         } else {
             None
         }
@@ -103,26 +250,36 @@ impl Dielectric {
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<(Ray, Vector3<f64>)> {
-        let attenuation = Vector3::new(1.0, 1.0, 1.0);
-        let (outward_normal, ni_over_nt, cosine) = if ray.direction().dot(&hit.normal) > 0.0 {
-            let cosine =
-                self.ref_idx * ray.direction().dot(&hit.normal) / ray.direction().magnitude();
-            (-hit.normal, self.ref_idx, cosine)
+    // reflection or refraction, picked with the probability of the fresnel
+    // term, so both lobes have a weight of 1
+    fn sample(
+        &self,
+        hit: &HitRecord,
+        wo: &Vector3<f64>,
+        sampler: &mut dyn Sampler,
+    ) -> Option<BsdfSample> {
+        let direction = -wo;
+        let (outward_normal, ni_over_nt, cosine) = if direction.dot(&hit.normal) > 0.0 {
+            (
+                -hit.normal,
+                self.ref_idx,
+                self.ref_idx * direction.dot(&hit.normal),
+            )
         } else {
-            let cosine = -ray.direction().dot(&hit.normal) / ray.direction().magnitude();
-            (hit.normal, 1.0 / self.ref_idx, cosine)
+            (hit.normal, 1.0 / self.ref_idx, -direction.dot(&hit.normal))
         };
-        if let Some(refracted) = refract(&ray.direction(), &outward_normal, ni_over_nt) {
-            let reflect_prob = schlick(cosine, self.ref_idx);
-            if rand::thread_rng().gen::<f64>() >= reflect_prob {
-                let scattered = Ray::new(hit.p, refracted, ray.time());
-                return Some((scattered, attenuation));
-            }
-        }
-        let reflected = reflect(&ray.direction(), &hit.normal);
-        let scattered = Ray::new(hit.p, reflected, ray.time());
-        Some((scattered, attenuation))
+        let reflect_prob = schlick(cosine, self.ref_idx);
+        let (wi, lobe) = match refract(&direction, &outward_normal, ni_over_nt) {
+            Some(refracted) if sampler.next_1d() >= reflect_prob => (refracted, Lobe::Transmission),
+            _ => (reflect(&direction, &hit.normal), Lobe::Specular),
+        };
+        Some(BsdfSample {
+            wi: wi.normalize(),
+            f: Vector3::new(1.0, 1.0, 1.0),
+            pdf: 1.0,
+            delta: true,
+            lobe,
+        })
     }
 
     fn emitted(&self, _u: f64, _v: f64, _p: &Vector3<f64>) -> Vector3<f64> {
@@ -140,14 +297,23 @@ impl<T: Texture> DiffuseLight<T> {
     }
 }
 
-impl<T: Texture + std::marker::Sync> Material for DiffuseLight<T> {
-    fn scatter(&self, _ray: &Ray, _hit: &HitRecord) -> Option<(Ray, Vector3<f64>)> {
+impl<T: Texture> Material for DiffuseLight<T> {
+    fn sample(
+        &self,
+        _hit: &HitRecord,
+        _wo: &Vector3<f64>,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<BsdfSample> {
         None
     }
 
     fn emitted(&self, u: f64, v: f64, p: &Vector3<f64>) -> Vector3<f64> {
         self.emit.value(u, v, &p)
     }
+
+    fn is_emissive(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Clone)]
@@ -161,13 +327,33 @@ impl<T: Texture> Isotropic<T> {
     }
 }
 
-impl<T: Texture + std::marker::Sync> Material for Isotropic<T> {
-    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<(Ray, Vector3<f64>)> {
-        let scattered = Ray::new(hit.p, random_in_unit_sphere(), ray.time());
-        Some((scattered, self.albedo.value(hit.u, hit.v, &hit.p)))
+impl<T: Texture> Material for Isotropic<T> {
+    fn sample(
+        &self,
+        hit: &HitRecord,
+        wo: &Vector3<f64>,
+        sampler: &mut dyn Sampler,
+    ) -> Option<BsdfSample> {
+        let wi = random_unit_vector(sampler);
+        Some(BsdfSample {
+            wi,
+            f: self.eval(hit, wo, &wi),
+            pdf: self.pdf(hit, wo, &wi),
+            delta: false,
+            lobe: Lobe::Volume,
+        })
     }
 
     fn emitted(&self, _u: f64, _v: f64, _p: &Vector3<f64>) -> Vector3<f64> {
         Vector3::zeros()
     }
+
+    fn eval(&self, hit: &HitRecord, wo: &Vector3<f64>, wi: &Vector3<f64>) -> Vector3<f64> {
+        self.albedo.value_at(hit) * self.pdf(hit, wo, wi)
+    }
+
+    // every direction is equally likely
+    fn pdf(&self, _hit: &HitRecord, _wo: &Vector3<f64>, _wi: &Vector3<f64>) -> f64 {
+        1.0 / (4.0 * f64::consts::PI)
+    }
 }
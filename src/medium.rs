@@ -1,11 +1,11 @@
 use nalgebra::Vector3;
-use rand::Rng;
 use std::f64;
 
 use crate::aabb::AABB;
 use crate::hittable::{HitRecord, Hittable};
 use crate::material::Isotropic;
 use crate::ray::Ray;
+use crate::sampler;
 use crate::texture::Texture;
 
 pub struct ConstantMedium<H: Hittable, T: Texture> {
@@ -24,11 +24,29 @@ impl<H: Hittable, T: Texture> ConstantMedium<H, T> {
     }
 }
 
-impl<H: Hittable, T: Texture + std::marker::Sync> Hittable for ConstantMedium<H, T> {
+// the ray and the point where it enters the boundary, so every medium the
+// ray crosses draws its own distance
+fn ray_hash(ray: &Ray, entry: &Vector3<f64>) -> u64 {
+    let (o, d) = (ray.origin(), ray.direction());
+    sampler::hash(&[
+        o.x.to_bits(),
+        o.y.to_bits(),
+        o.z.to_bits(),
+        d.x.to_bits(),
+        d.y.to_bits(),
+        d.z.to_bits(),
+        ray.time().to_bits(),
+        entry.x.to_bits(),
+        entry.y.to_bits(),
+        entry.z.to_bits(),
+    ])
+}
+
+impl<H: Hittable, T: Texture> Hittable for ConstantMedium<H, T> {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let mut rng = rand::thread_rng();
         if let Some(mut hit1) = self.boundary.hit(&ray, -f64::MAX, f64::MAX) {
             if let Some(mut hit2) = self.boundary.hit(&ray, hit1.t + 0.0001, f64::MAX) {
+                let entry = hit1.p;
                 if hit1.t < t_min {
                     hit1.t = t_min
                 }
@@ -37,7 +55,11 @@ impl<H: Hittable, T: Texture + std::marker::Sync> Hittable for ConstantMedium<H,
                 }
                 if hit1.t < hit2.t {
                     let distance_inside_boundary = (hit2.t - hit1.t) * ray.direction().norm();
-                    let hit_distance = -(1.0 / self.density) * rng.gen::<f64>().ln();
+                    // hit has no sampler, the distance comes from a hash of
+                    // the ray and of the entry point so the same ray always
+                    // scatters at the same point of the same medium
+                    let r = 1.0 - sampler::to_unit(ray_hash(ray, &entry));
+                    let hit_distance = -(1.0 / self.density) * r.ln();
                     if hit_distance < distance_inside_boundary {
                         let t = hit1.t + hit_distance / ray.direction().norm();
                         return Some(HitRecord {
@@ -47,6 +69,7 @@ impl<H: Hittable, T: Texture + std::marker::Sync> Hittable for ConstantMedium<H,
                             p: ray.pointing_at(t),
                             normal: Vector3::new(1.0, 0.0, 0.0), // choose arbitrary
                             material: &self.phase_function,
+                            vertex_color: None,
                         });
                     }
                 }
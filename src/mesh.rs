@@ -0,0 +1,112 @@
+use nalgebra::{Vector2, Vector3};
+
+use crate::aabb::AABB;
+use crate::bvh::{BVHStats, BVHTree};
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::triangle;
+
+// a triangle mesh with shared vertex buffers: every triangle is three indices
+// into them. the optional normals, uvs and colors are per vertex, so they use
+// the same indices of the positions. the mesh has its own BVH over the triangles
+pub struct TriangleMesh<M: Material> {
+    vertices: Vec<Vector3<f64>>,
+    normals: Option<Vec<Vector3<f64>>>,
+    uvs: Option<Vec<Vector2<f64>>>,
+    colors: Option<Vec<Vector3<f64>>>,
+    indices: Vec<[usize; 3]>,
+    tree: BVHTree,
+    material: M,
+}
+
+impl<M: Material> TriangleMesh<M> {
+    pub fn new(vertices: Vec<Vector3<f64>>, indices: Vec<[usize; 3]>, material: M) -> Self {
+        if indices.iter().flatten().any(|&i| i >= vertices.len()) {
+            panic!["Triangle index out of the vertex buffer"]
+        }
+        let boxes: Vec<AABB> = indices
+            .iter()
+            .map(|&[a, b, c]| triangle::bounding_box(&[vertices[a], vertices[b], vertices[c]]))
+            .collect();
+        let (tree, order) = BVHTree::build(&boxes);
+        // the triangles are stored in the order of the leaves of the tree
+        let indices = order.iter().map(|&i| indices[i]).collect();
+        Self {
+            vertices,
+            normals: None,
+            uvs: None,
+            colors: None,
+            indices,
+            tree,
+            material,
+        }
+    }
+
+    pub fn with_normals(mut self, normals: Vec<Vector3<f64>>) -> Self {
+        if normals.len() != self.vertices.len() {
+            panic!["A mesh needs one normal per vertex"]
+        }
+        self.normals = Some(normals);
+        self
+    }
+
+    pub fn with_uvs(mut self, uvs: Vec<Vector2<f64>>) -> Self {
+        if uvs.len() != self.vertices.len() {
+            panic!["A mesh needs one uv per vertex"]
+        }
+        self.uvs = Some(uvs);
+        self
+    }
+
+    // the colors reach the material through a VertexColorTexture
+    pub fn with_colors(mut self, colors: Vec<Vector3<f64>>) -> Self {
+        if colors.len() != self.vertices.len() {
+            panic!["A mesh needs one color per vertex"]
+        }
+        self.colors = Some(colors);
+        self
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn stats(&self) -> BVHStats {
+        self.tree.stats()
+    }
+}
+
+impl<M: Material> Hittable for TriangleMesh<M> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.tree.hit(ray, t_min, t_max, |i, closest_so_far| {
+            let [a, b, c] = self.indices[i];
+            let vertices = [self.vertices[a], self.vertices[b], self.vertices[c]];
+            triangle::intersect(ray, &vertices, t_min, closest_so_far).map(|(t, b1, b2)| {
+                let normals = self.normals.as_ref().map(|n| [n[a], n[b], n[c]]);
+                let uvs = self.uvs.as_ref().map(|uv| [uv[a], uv[b], uv[c]]);
+                let (u, v) = triangle::texture_uv(uvs.as_ref(), b1, b2);
+                HitRecord {
+                    t,
+                    u,
+                    v,
+                    p: ray.pointing_at(t),
+                    normal: triangle::shading_normal(&vertices, normals.as_ref(), b1, b2),
+                    material: &self.material,
+                    vertex_color: self
+                        .colors
+                        .as_ref()
+                        .map(|col| triangle::interpolate(&[col[a], col[b], col[c]], b1, b2)),
+                }
+            })
+        })
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<AABB> {
+        self.tree.bounding_box()
+    }
+}
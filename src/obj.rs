@@ -0,0 +1,382 @@
+use nalgebra::{Vector2, Vector3};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::bvh::BVHNode;
+use crate::hittable::Hittable;
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::mesh::TriangleMesh;
+use crate::texture::{ImageTexture, SolidTexture};
+
+/*
+* Wavefront OBJ and MTL reader
+*/
+#[derive(Debug)]
+pub struct ObjError {
+    pub path: PathBuf,
+    // 0 when the error is not about a specific line (e.g. a missing file)
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line > 0 {
+            write!(f, "{}:{}: {}", self.path.display(), self.line, self.message)
+        } else {
+            write!(f, "{}: {}", self.path.display(), self.message)
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+fn read(path: &Path) -> Result<String, ObjError> {
+    fs::read_to_string(path).map_err(|e| ObjError {
+        path: path.to_owned(),
+        line: 0,
+        message: e.to_string(),
+    })
+}
+
+// runs f on every meaningful line of the file, without comments, and adds
+// the line number to the errors
+fn for_each_line<F>(path: &Path, source: &str, mut f: F) -> Result<(), ObjError>
+where
+    F: FnMut(&str, &[&str]) -> Result<(), String>,
+{
+    for (n, line) in source.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let mut tokens = line.split_whitespace();
+        if let Some(keyword) = tokens.next() {
+            let args: Vec<&str> = tokens.collect();
+            f(keyword, &args).map_err(|message| ObjError {
+                path: path.to_owned(),
+                line: n + 1,
+                message,
+            })?;
+        }
+    }
+    Ok(())
+}
+
+fn parse_f64(token: &str) -> Result<f64, String> {
+    token
+        .parse::<f64>()
+        .map_err(|_| format!("'{}' is not a number", token))
+}
+
+fn parse_floats(keyword: &str, args: &[&str], min: usize) -> Result<Vec<f64>, String> {
+    if args.len() < min {
+        return Err(format!("'{}' needs at least {} values", keyword, min));
+    }
+    args.iter().map(|a| parse_f64(a)).collect()
+}
+
+fn parse_color(keyword: &str, args: &[&str]) -> Result<Vector3<f64>, String> {
+    let c = parse_floats(keyword, args, 3)?;
+    Ok(Vector3::new(c[0], c[1], c[2]))
+}
+
+#[derive(Clone, Default)]
+struct MtlDescription {
+    kd: Vector3<f64>,
+    ks: Vector3<f64>,
+    ke: Vector3<f64>,
+    ns: f64,
+    ni: f64,
+    d: f64,
+    map_kd: Option<ImageTexture>,
+}
+
+impl MtlDescription {
+    fn new() -> Self {
+        Self {
+            kd: Vector3::new(0.8, 0.8, 0.8),
+            ni: 1.5,
+            d: 1.0,
+            ..Default::default()
+        }
+    }
+
+    // OBJ materials are phong-like, each one is mapped on the closest
+    // material of the renderer: emitters first, then glass, metal and diffuse
+    fn build(self) -> Arc<dyn Material> {
+        if self.ke.max() > 0.0 {
+            Arc::new(DiffuseLight::new(SolidTexture::new(
+                self.ke.x, self.ke.y, self.ke.z,
+            )))
+        } else if self.d < 1.0 {
+            Arc::new(Dielectric::new(self.ni))
+        } else if self.ks.max() > self.kd.max() {
+            // the sharper the highlight, the less fuzzy the reflection
+            let fuzz = (2.0 / (self.ns + 2.0)).sqrt();
+            Arc::new(Metal::new(
+                SolidTexture::new(self.ks.x, self.ks.y, self.ks.z),
+                fuzz,
+            ))
+        } else {
+            match self.map_kd {
+                Some(texture) => Arc::new(Lambertian::new(texture)),
+                None => Arc::new(Lambertian::new(SolidTexture::new(
+                    self.kd.x, self.kd.y, self.kd.z,
+                ))),
+            }
+        }
+    }
+}
+
+// reads a MTL library, the names of the materials are the keys of the map.
+// the texture files are relative to the library
+pub fn load_mtl<P: AsRef<Path>>(path: P) -> Result<HashMap<String, Arc<dyn Material>>, ObjError> {
+    let path = path.as_ref();
+    let source = read(path)?;
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlDescription)> = None;
+    for_each_line(path, &source, |keyword, args| {
+        if keyword == "newmtl" {
+            if args.is_empty() {
+                return Err("'newmtl' needs a name".to_owned());
+            }
+            if let Some((name, description)) = current.take() {
+                materials.insert(name, description.build());
+            }
+            current = Some((args.join(" "), MtlDescription::new()));
+            return Ok(());
+        }
+        let description = match current.as_mut() {
+            Some((_, description)) => description,
+            None => return Err(format!("'{}' before any 'newmtl'", keyword)),
+        };
+        match keyword {
+            "Kd" => description.kd = parse_color(keyword, args)?,
+            "Ks" => description.ks = parse_color(keyword, args)?,
+            "Ke" => description.ke = parse_color(keyword, args)?,
+            "Ns" => description.ns = parse_floats(keyword, args, 1)?[0],
+            "Ni" => description.ni = parse_floats(keyword, args, 1)?[0],
+            "d" => description.d = parse_floats(keyword, args, 1)?[0],
+            "Tr" => description.d = 1.0 - parse_floats(keyword, args, 1)?[0],
+            "map_Kd" => {
+                // the options of the map come before the file name
+                let file = match args.last() {
+                    Some(file) => directory.join(file),
+                    None => return Err("'map_Kd' needs a file".to_owned()),
+                };
+                let texture = ImageTexture::open(&file)
+                    .map_err(|e| format!("can't read {}: {}", file.display(), e))?;
+                description.map_kd = Some(texture);
+            }
+            // the other statements don't change the mapped material
+            _ => {}
+        }
+        Ok(())
+    })?;
+    if let Some((name, description)) = current.take() {
+        materials.insert(name, description.build());
+    }
+    Ok(materials)
+}
+
+// a vertex of a face: indices of position, texture coordinate and normal
+type FaceVertex = (usize, Option<usize>, Option<usize>);
+
+// the triangles that share the same group and the same material
+struct Part {
+    material: Arc<dyn Material>,
+    triangles: Vec<[FaceVertex; 3]>,
+}
+
+// OBJ indices start from 1, negative ones count back from the last element
+fn resolve(token: &str, count: usize, what: &str) -> Result<usize, String> {
+    let index = token
+        .parse::<i64>()
+        .map_err(|_| format!("'{}' is not a valid {} index", token, what))?;
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        count as i64 + index
+    };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        Err(format!("{} index {} is out of range", what, index))
+    } else {
+        Ok(resolved as usize)
+    }
+}
+
+fn parse_face_vertex(
+    token: &str,
+    positions: usize,
+    uvs: usize,
+    normals: usize,
+) -> Result<FaceVertex, String> {
+    let mut parts = token.split('/');
+    let v = resolve(parts.next().unwrap_or(""), positions, "vertex")?;
+    let vt = match parts.next() {
+        Some(t) if !t.is_empty() => Some(resolve(t, uvs, "texture")?),
+        _ => None,
+    };
+    let vn = match parts.next() {
+        Some(n) if !n.is_empty() => Some(resolve(n, normals, "normal")?),
+        _ => None,
+    };
+    if parts.next().is_some() {
+        return Err(format!("'{}' is not a valid face vertex", token));
+    }
+    Ok((v, vt, vn))
+}
+
+// reads an OBJ file and its material libraries. every group is split by
+// material into meshes, and the meshes are collected into a BVH
+pub fn load<P: AsRef<Path>>(path: P) -> Result<BVHNode, ObjError> {
+    let path = path.as_ref();
+    let source = read(path)?;
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let default_material: Arc<dyn Material> =
+        Arc::new(Lambertian::new(SolidTexture::new(0.73, 0.73, 0.73)));
+    let mut materials: HashMap<String, Arc<dyn Material>> = HashMap::new();
+    let mut positions: Vec<Vector3<f64>> = Vec::new();
+    let mut uvs: Vec<Vector2<f64>> = Vec::new();
+    let mut normals: Vec<Vector3<f64>> = Vec::new();
+
+    let mut parts: Vec<Part> = Vec::new();
+    let mut part_index: HashMap<(String, String), usize> = HashMap::new();
+    let mut group = String::new();
+    let mut material_name = String::new();
+
+    for_each_line(path, &source, |keyword, args| {
+        match keyword {
+            "v" => {
+                let v = parse_floats(keyword, args, 3)?;
+                positions.push(Vector3::new(v[0], v[1], v[2]));
+            }
+            "vt" => {
+                let t = parse_floats(keyword, args, 1)?;
+                uvs.push(Vector2::new(t[0], t.get(1).copied().unwrap_or(0.0)));
+            }
+            "vn" => {
+                let n = parse_floats(keyword, args, 3)?;
+                normals.push(Vector3::new(n[0], n[1], n[2]));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err("a face needs at least 3 vertices".to_owned());
+                }
+                let face = args
+                    .iter()
+                    .map(|a| parse_face_vertex(a, positions.len(), uvs.len(), normals.len()))
+                    .collect::<Result<Vec<FaceVertex>, String>>()?;
+                let key = (group.clone(), material_name.clone());
+                let index = match part_index.get(&key) {
+                    Some(&index) => index,
+                    None => {
+                        let material = if material_name.is_empty() {
+                            default_material.clone()
+                        } else {
+                            match materials.get(&material_name) {
+                                Some(material) => material.clone(),
+                                None => {
+                                    return Err(format!(
+                                        "material '{}' is not defined",
+                                        material_name
+                                    ))
+                                }
+                            }
+                        };
+                        parts.push(Part {
+                            material,
+                            triangles: Vec::new(),
+                        });
+                        part_index.insert(key, parts.len() - 1);
+                        parts.len() - 1
+                    }
+                };
+                // polygons are split in a fan of triangles around the first vertex
+                for i in 1..face.len() - 1 {
+                    parts[index].triangles.push([face[0], face[i], face[i + 1]]);
+                }
+            }
+            "g" | "o" => group = args.join(" "),
+            "usemtl" => {
+                if args.is_empty() {
+                    return Err("'usemtl' needs a material name".to_owned());
+                }
+                material_name = args.join(" ");
+            }
+            "mtllib" => {
+                if args.is_empty() {
+                    return Err("'mtllib' needs a file".to_owned());
+                }
+                for file in args {
+                    let library = load_mtl(directory.join(file)).map_err(|e| e.to_string())?;
+                    materials.extend(library);
+                }
+            }
+            // smoothing groups, lines, points and free form geometry are not used
+            _ => {}
+        }
+        Ok(())
+    })?;
+
+    if parts.is_empty() {
+        return Err(ObjError {
+            path: path.to_owned(),
+            line: 0,
+            message: "the file has no faces".to_owned(),
+        });
+    }
+    let mut meshes: Vec<Arc<dyn Hittable>> = parts
+        .into_iter()
+        .map(|part| build_mesh(part, &positions, &uvs, &normals))
+        .collect();
+    Ok(BVHNode::new(&mut meshes, 0.0, 1.0))
+}
+
+fn build_mesh(
+    part: Part,
+    positions: &[Vector3<f64>],
+    uvs: &[Vector2<f64>],
+    normals: &[Vector3<f64>],
+) -> Arc<dyn Hittable> {
+    // the mesh indexes all its buffers with one index, every distinct
+    // combination of position, uv and normal becomes a vertex of the mesh
+    let mut vertex_index: HashMap<FaceVertex, usize> = HashMap::new();
+    let mut vertices: Vec<FaceVertex> = Vec::new();
+    let mut indices = Vec::with_capacity(part.triangles.len());
+    for triangle in part.triangles.iter() {
+        let mut face = [0; 3];
+        for (k, vertex) in triangle.iter().enumerate() {
+            face[k] = *vertex_index.entry(*vertex).or_insert_with(|| {
+                vertices.push(*vertex);
+                vertices.len() - 1
+            });
+        }
+        indices.push(face);
+    }
+
+    let mesh_positions = vertices.iter().map(|&(v, _, _)| positions[v]).collect();
+    let mut mesh = TriangleMesh::new(mesh_positions, indices, part.material);
+    // uvs and normals are used only when every vertex of the part has them
+    if vertices.iter().all(|&(_, vt, _)| vt.is_some()) {
+        mesh = mesh.with_uvs(
+            vertices
+                .iter()
+                .map(|&(_, vt, _)| uvs[vt.unwrap()])
+                .collect(),
+        );
+    }
+    if vertices.iter().all(|&(_, _, vn)| vn.is_some()) {
+        mesh = mesh.with_normals(
+            vertices
+                .iter()
+                .map(|&(_, _, vn)| normals[vn.unwrap()].normalize())
+                .collect(),
+        );
+    }
+    Arc::new(mesh)
+}
@@ -0,0 +1,111 @@
+use image::codecs::hdr::HdrEncoder;
+use image::{ImageBuffer, ImageFormat, ImageResult, Rgb, Rgb32FImage, RgbImage};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+
+use crate::renderer::FrameBuffer;
+use crate::tonemap::ToneMapping;
+
+/*
+* Image files for a frame buffer. The 8 and 16 bit formats get the tone
+* mapped colors, the float formats keep the linear radiance as it is, without
+* clamping, so the bright lights survive for compositing
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    // ASCII P3, like FrameBuffer::to_ppm
+    Ppm,
+    Png,
+    Png16,
+    Exr,
+    Hdr,
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "ppm" => Some(OutputFormat::Ppm),
+            "png" => Some(OutputFormat::Png),
+            "png16" => Some(OutputFormat::Png16),
+            "exr" => Some(OutputFormat::Exr),
+            "hdr" => Some(OutputFormat::Hdr),
+            _ => None,
+        }
+    }
+
+    // from the extension of the file, a .png is 8 bit
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        path.as_ref()
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(Self::from_name)
+            .filter(|&format| format != OutputFormat::Png16)
+    }
+
+    pub fn is_float(self) -> bool {
+        matches!(self, OutputFormat::Exr | OutputFormat::Hdr)
+    }
+}
+
+pub fn to_rgb8(frame: &FrameBuffer, tone_mapping: &ToneMapping) -> RgbImage {
+    let data = frame
+        .pixels()
+        .iter()
+        .map(|p| tone_mapping.apply(p))
+        .flat_map(|p| [p.x, p.y, p.z])
+        .map(|c| (255.0 * c).round() as u8)
+        .collect();
+    ImageBuffer::from_raw(frame.width() as u32, frame.height() as u32, data).unwrap()
+}
+
+pub fn to_rgb16(
+    frame: &FrameBuffer,
+    tone_mapping: &ToneMapping,
+) -> ImageBuffer<Rgb<u16>, Vec<u16>> {
+    let data = frame
+        .pixels()
+        .iter()
+        .map(|p| tone_mapping.apply(p))
+        .flat_map(|p| [p.x, p.y, p.z])
+        .map(|c| (65535.0 * c).round() as u16)
+        .collect();
+    ImageBuffer::from_raw(frame.width() as u32, frame.height() as u32, data).unwrap()
+}
+
+// linear and unclamped
+pub fn to_rgb32f(frame: &FrameBuffer) -> Rgb32FImage {
+    let data = frame
+        .pixels()
+        .iter()
+        .flat_map(|p| [p.x, p.y, p.z])
+        .map(|c| c as f32)
+        .collect();
+    ImageBuffer::from_raw(frame.width() as u32, frame.height() as u32, data).unwrap()
+}
+
+// the tone mapping is ignored by the float formats
+pub fn save<P: AsRef<Path>>(
+    frame: &FrameBuffer,
+    path: P,
+    format: OutputFormat,
+    tone_mapping: &ToneMapping,
+) -> ImageResult<()> {
+    let path = path.as_ref();
+    match format {
+        OutputFormat::Ppm => {
+            fs::write(path, frame.to_ppm(tone_mapping)).map_err(image::ImageError::IoError)
+        }
+        OutputFormat::Png => to_rgb8(frame, tone_mapping).save_with_format(path, ImageFormat::Png),
+        OutputFormat::Png16 => {
+            to_rgb16(frame, tone_mapping).save_with_format(path, ImageFormat::Png)
+        }
+        OutputFormat::Exr => to_rgb32f(frame).save_with_format(path, ImageFormat::OpenExr),
+        // the generic save of the image crate cannot write .hdr files
+        OutputFormat::Hdr => {
+            let pixels: Vec<Rgb<f32>> = to_rgb32f(frame).pixels().copied().collect();
+            let file = BufWriter::new(File::create(path)?);
+            HdrEncoder::new(file).encode(&pixels, frame.width(), frame.height())
+        }
+    }
+}
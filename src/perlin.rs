@@ -1,16 +1,15 @@
 use nalgebra::Vector3;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
-fn permute(p: &mut [usize], n: usize) {
-    let mut rng = rand::thread_rng();
+fn permute(rng: &mut StdRng, p: &mut [usize], n: usize) {
     for i in (0..n as usize).rev() {
         let target = rng.gen_range(0..(i + 1));
         p.swap(i, target);
     }
 }
 
-fn perlin_generate() -> Vec<Vector3<f64>> {
-    let mut rng = rand::thread_rng();
+fn perlin_generate(rng: &mut StdRng) -> Vec<Vector3<f64>> {
     let mut p = Vec::with_capacity(256);
     for _ in 0..256 {
         p.push(
@@ -25,13 +24,12 @@ fn perlin_generate() -> Vec<Vector3<f64>> {
     p
 }
 
-fn perlin_generate_perm() -> Vec<usize> {
-    // let mut rng = rand::thread_rng();
+fn perlin_generate_perm(rng: &mut StdRng) -> Vec<usize> {
     let mut p = Vec::with_capacity(256);
     for i in 0..256 {
         p.push(i);
     }
-    permute(&mut p, 256);
+    permute(rng, &mut p, 256);
     p
 }
 
@@ -64,12 +62,18 @@ pub struct Perlin {
 }
 
 impl Perlin {
+    // the same noise every time, so the renders do not change
     pub fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
         Self {
-            ran_vec: perlin_generate(),
-            perm_x: perlin_generate_perm(),
-            perm_y: perlin_generate_perm(),
-            perm_z: perlin_generate_perm(),
+            ran_vec: perlin_generate(&mut rng),
+            perm_x: perlin_generate_perm(&mut rng),
+            perm_y: perlin_generate_perm(&mut rng),
+            perm_z: perlin_generate_perm(&mut rng),
         }
     }
 
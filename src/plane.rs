@@ -0,0 +1,95 @@
+use nalgebra::Vector3;
+use std::f64;
+
+use crate::aabb::AABB;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::util::{orthonormal_basis, random_unit_vector};
+
+/*
+* A plane without borders, e.g. a ground that reaches the horizon. It has no
+* bounding box: a BVH keeps it apart from the bounded objects. The uv of a hit
+* are the coordinates along two axes of the plane, wrapped in [0, 1), so an
+* image texture repeats every unit
+*/
+pub struct InfinitePlane<M: Material> {
+    point: Vector3<f64>,
+    normal: Vector3<f64>,
+    tangent: Vector3<f64>,
+    bitangent: Vector3<f64>,
+    material: M,
+}
+
+impl<M: Material> InfinitePlane<M> {
+    pub fn new(point: Vector3<f64>, normal: Vector3<f64>, material: M) -> Self {
+        let normal = normal.normalize();
+        let (tangent, bitangent) = orthonormal_basis(&normal);
+        Self {
+            point,
+            normal,
+            tangent,
+            bitangent,
+            material,
+        }
+    }
+
+    // the distance from p along the normal, negative behind the plane
+    fn height(&self, p: &Vector3<f64>) -> f64 {
+        self.normal.dot(&(p - self.point))
+    }
+}
+
+impl<M: Material> Hittable for InfinitePlane<M> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let denominator = self.normal.dot(&ray.direction());
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let t = -self.height(&ray.origin()) / denominator;
+        if t < t_min || t > t_max {
+            return None;
+        }
+        let p = ray.pointing_at(t);
+        let d = p - self.point;
+        Some(HitRecord {
+            t,
+            u: d.dot(&self.tangent).rem_euclid(1.0),
+            v: d.dot(&self.bitangent).rem_euclid(1.0),
+            p,
+            normal: self.normal,
+            material: &self.material,
+            vertex_color: None,
+        })
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<AABB> {
+        None
+    }
+
+    // the plane covers the half of the directions that point toward it, they
+    // are sampled uniformly: the area of the plane cannot be
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Vector3<f64>) -> f64 {
+        if self.height(origin) * self.normal.dot(direction) < 0.0 {
+            1.0 / (2.0 * f64::consts::PI)
+        } else {
+            0.0
+        }
+    }
+
+    fn random(&self, origin: &Vector3<f64>, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        let direction = random_unit_vector(sampler);
+        if self.height(origin) * self.normal.dot(&direction) > 0.0 {
+            -direction
+        } else {
+            direction
+        }
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        if self.material.is_emissive() {
+            lights.push(self);
+        }
+    }
+}
@@ -0,0 +1,459 @@
+use nalgebra::{Vector2, Vector3};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::material::Material;
+use crate::mesh::TriangleMesh;
+
+/*
+* Stanford PLY reader, ascii and binary (both little and big endian)
+*/
+#[derive(Debug)]
+pub struct PlyError {
+    pub path: PathBuf,
+    // line of the header or of the ascii body, 0 for binary data
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for PlyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line > 0 {
+            write!(f, "{}:{}: {}", self.path.display(), self.line, self.message)
+        } else {
+            write!(f, "{}: {}", self.path.display(), self.message)
+        }
+    }
+}
+
+impl std::error::Error for PlyError {}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+fn parse_scalar(name: &str) -> Result<Scalar, String> {
+    match name {
+        "char" | "int8" => Ok(Scalar::I8),
+        "uchar" | "uint8" => Ok(Scalar::U8),
+        "short" | "int16" => Ok(Scalar::I16),
+        "ushort" | "uint16" => Ok(Scalar::U16),
+        "int" | "int32" => Ok(Scalar::I32),
+        "uint" | "uint32" => Ok(Scalar::U32),
+        "float" | "float32" => Ok(Scalar::F32),
+        "double" | "float64" => Ok(Scalar::F64),
+        _ => Err(format!("unknown property type '{}'", name)),
+    }
+}
+
+impl Scalar {
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    // integer colors go from 0 to the biggest value of their type
+    fn color_scale(self) -> f64 {
+        match self {
+            Scalar::U8 => 1.0 / 255.0,
+            Scalar::U16 => 1.0 / 65535.0,
+            _ => 1.0,
+        }
+    }
+}
+
+enum Property {
+    Scalar(Scalar, String),
+    List(Scalar, Scalar, String),
+}
+
+impl Property {
+    fn name(&self) -> &str {
+        match self {
+            Property::Scalar(_, name) | Property::List(_, _, name) => name,
+        }
+    }
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+struct Header {
+    format: Format,
+    elements: Vec<Element>,
+    // number of lines and of bytes taken by the header
+    lines: usize,
+    size: usize,
+}
+
+fn parse_header(data: &[u8]) -> Result<Header, (usize, String)> {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut offset = 0;
+    let mut n = 0;
+    loop {
+        let end = match data[offset..].iter().position(|&b| b == b'\n') {
+            Some(end) => offset + end,
+            None => return Err((n + 1, "the header has no 'end_header'".to_owned())),
+        };
+        let line = String::from_utf8_lossy(&data[offset..end]).into_owned();
+        offset = end + 1;
+        n += 1;
+
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.first().copied() {
+            _ if n == 1 => {
+                if tokens != ["ply"] {
+                    return Err((n, "not a PLY file".to_owned()));
+                }
+            }
+            Some("format") => {
+                format = Some(match tokens.get(1).copied() {
+                    Some("ascii") => Format::Ascii,
+                    Some("binary_little_endian") => Format::BinaryLittleEndian,
+                    Some("binary_big_endian") => Format::BinaryBigEndian,
+                    _ => return Err((n, format!("unknown format '{}'", line.trim()))),
+                })
+            }
+            Some("element") => {
+                if tokens.len() != 3 {
+                    return Err((n, "'element' needs a name and a count".to_owned()));
+                }
+                let count = tokens[2]
+                    .parse::<usize>()
+                    .map_err(|_| (n, format!("'{}' is not a valid count", tokens[2])))?;
+                elements.push(Element {
+                    name: tokens[1].to_owned(),
+                    count,
+                    properties: Vec::new(),
+                });
+            }
+            Some("property") => {
+                let element = match elements.last_mut() {
+                    Some(element) => element,
+                    None => return Err((n, "'property' before any 'element'".to_owned())),
+                };
+                let property = match tokens[1..] {
+                    ["list", count, item, name] => Property::List(
+                        parse_scalar(count).map_err(|e| (n, e))?,
+                        parse_scalar(item).map_err(|e| (n, e))?,
+                        name.to_owned(),
+                    ),
+                    [ty, name] => {
+                        Property::Scalar(parse_scalar(ty).map_err(|e| (n, e))?, name.to_owned())
+                    }
+                    _ => return Err((n, format!("malformed property '{}'", line.trim()))),
+                };
+                element.properties.push(property);
+            }
+            Some("end_header") => break,
+            Some("comment") | Some("obj_info") | None => {}
+            Some(keyword) => return Err((n, format!("unknown header keyword '{}'", keyword))),
+        }
+    }
+    match format {
+        Some(format) => Ok(Header {
+            format,
+            elements,
+            lines: n,
+            size: offset,
+        }),
+        None => Err((n, "the header has no 'format'".to_owned())),
+    }
+}
+
+// a property value of an element, lists are used only for the faces
+enum Value {
+    Scalar(f64),
+    List(Vec<f64>),
+}
+
+impl Value {
+    fn scalar(&self) -> f64 {
+        match self {
+            Value::Scalar(v) => *v,
+            Value::List(_) => 0.0,
+        }
+    }
+}
+
+trait Body {
+    fn next(&mut self, ty: Scalar) -> Result<f64, String>;
+    // called when an element ends
+    fn end_element(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+    // the line being read, 0 when the body is not text
+    fn line(&self) -> usize {
+        0
+    }
+}
+
+struct AsciiBody<'a> {
+    lines: std::str::Lines<'a>,
+    tokens: std::vec::IntoIter<&'a str>,
+    line: usize,
+}
+
+impl<'a> Body for AsciiBody<'a> {
+    fn next(&mut self, _ty: Scalar) -> Result<f64, String> {
+        loop {
+            if let Some(token) = self.tokens.next() {
+                return token
+                    .parse::<f64>()
+                    .map_err(|_| format!("'{}' is not a number", token));
+            }
+            // every element starts on a new line
+            match self.lines.next() {
+                Some(line) => {
+                    self.line += 1;
+                    self.tokens = line.split_whitespace().collect::<Vec<_>>().into_iter();
+                }
+                None => return Err("unexpected end of file".to_owned()),
+            }
+        }
+    }
+
+    fn end_element(&mut self) -> Result<(), String> {
+        match self.tokens.next() {
+            Some(token) => Err(format!("unexpected value '{}'", token)),
+            None => Ok(()),
+        }
+    }
+
+    fn line(&self) -> usize {
+        self.line
+    }
+}
+
+struct BinaryBody<'a> {
+    data: &'a [u8],
+    offset: usize,
+    big_endian: bool,
+}
+
+impl<'a> Body for BinaryBody<'a> {
+    fn next(&mut self, ty: Scalar) -> Result<f64, String> {
+        let size = ty.size();
+        if self.offset + size > self.data.len() {
+            return Err("unexpected end of file".to_owned());
+        }
+        let mut bytes = [0u8; 8];
+        bytes[..size].copy_from_slice(&self.data[self.offset..self.offset + size]);
+        self.offset += size;
+        if self.big_endian {
+            bytes[..size].reverse();
+        }
+        // bytes are now little endian
+        let value = match ty {
+            Scalar::I8 => bytes[0] as i8 as f64,
+            Scalar::U8 => bytes[0] as f64,
+            Scalar::I16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            Scalar::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            Scalar::I32 => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+            Scalar::U32 => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+            Scalar::F32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+            Scalar::F64 => f64::from_le_bytes(bytes),
+        };
+        Ok(value)
+    }
+}
+
+fn read_element(body: &mut dyn Body, element: &Element) -> Result<Vec<Value>, String> {
+    let mut values = Vec::with_capacity(element.properties.len());
+    for property in element.properties.iter() {
+        match property {
+            Property::Scalar(ty, _) => values.push(Value::Scalar(body.next(*ty)?)),
+            Property::List(count_type, item_type, _) => {
+                let count = body.next(*count_type)?;
+                if count < 0.0 {
+                    return Err(format!("negative list size {}", count));
+                }
+                let list = (0..count as usize)
+                    .map(|_| body.next(*item_type))
+                    .collect::<Result<Vec<f64>, String>>()?;
+                values.push(Value::List(list));
+            }
+        }
+    }
+    body.end_element()?;
+    Ok(values)
+}
+
+fn find(element: &Element, names: &[&str]) -> Option<usize> {
+    element
+        .properties
+        .iter()
+        .position(|p| names.contains(&p.name()))
+}
+
+// reads a PLY model as a single mesh. normals, texture coordinates and
+// colors of the vertices are kept when present, the colors are read by a
+// VertexColorTexture in the material
+pub fn load<P: AsRef<Path>, M: Material>(
+    path: P,
+    material: M,
+) -> Result<TriangleMesh<M>, PlyError> {
+    let path = path.as_ref();
+    let error = |line: usize, message: String| PlyError {
+        path: path.to_owned(),
+        line,
+        message,
+    };
+    let data = fs::read(path).map_err(|e| error(0, e.to_string()))?;
+    let header = parse_header(&data).map_err(|(line, message)| error(line, message))?;
+
+    let body_data = &data[header.size..];
+    let text;
+    let mut ascii;
+    let mut binary;
+    let body: &mut dyn Body = match header.format {
+        Format::Ascii => {
+            text = String::from_utf8_lossy(body_data);
+            ascii = AsciiBody {
+                lines: text.lines(),
+                tokens: Vec::new().into_iter(),
+                line: header.lines,
+            };
+            &mut ascii
+        }
+        format => {
+            binary = BinaryBody {
+                data: body_data,
+                offset: 0,
+                big_endian: format == Format::BinaryBigEndian,
+            };
+            &mut binary
+        }
+    };
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut colors = Vec::new();
+    // the vertex indices of every face with its number
+    let mut faces: Vec<(usize, Vec<usize>)> = Vec::new();
+    for element in header.elements.iter() {
+        let position = ["x", "y", "z"].map(|name| find(element, &[name]));
+        let normal = ["nx", "ny", "nz"].map(|name| find(element, &[name]));
+        let uv = [
+            find(element, &["u", "s", "texture_u", "texture_s"]),
+            find(element, &["v", "t", "texture_v", "texture_t"]),
+        ];
+        let color = [
+            find(element, &["red", "r", "diffuse_red"]),
+            find(element, &["green", "g", "diffuse_green"]),
+            find(element, &["blue", "b", "diffuse_blue"]),
+        ];
+        let face = find(element, &["vertex_indices", "vertex_index"]);
+        let color_scale: Vec<f64> = color
+            .iter()
+            .map(|c| match c.map(|i| &element.properties[i]) {
+                Some(Property::Scalar(ty, _)) => ty.color_scale(),
+                _ => 1.0,
+            })
+            .collect();
+
+        for instance in 0..element.count {
+            let values =
+                read_element(&mut *body, element).map_err(|message| match body.line() {
+                    0 => error(0, format!("{} {}: {}", element.name, instance, message)),
+                    line => error(line, message),
+                })?;
+            let get = |i: Option<usize>| i.map(|i| values[i].scalar());
+            if element.name == "vertex" {
+                match position {
+                    [Some(x), Some(y), Some(z)] => positions.push(Vector3::new(
+                        values[x].scalar(),
+                        values[y].scalar(),
+                        values[z].scalar(),
+                    )),
+                    _ => return Err(error(0, "the vertices have no position".to_owned())),
+                }
+                if let [Some(x), Some(y), Some(z)] = normal.map(get) {
+                    normals.push(Vector3::new(x, y, z));
+                }
+                if let [Some(u), Some(v)] = uv.map(get) {
+                    uvs.push(Vector2::new(u, v));
+                }
+                if let [Some(r), Some(g), Some(b)] = color.map(get) {
+                    colors.push(Vector3::new(
+                        r * color_scale[0],
+                        g * color_scale[1],
+                        b * color_scale[2],
+                    ));
+                }
+            } else if element.name == "face" {
+                let list = match face.map(|i| &values[i]) {
+                    Some(Value::List(list)) => list,
+                    _ => return Err(error(0, "the faces have no vertex indices".to_owned())),
+                };
+                if list.len() < 3 {
+                    return Err(error(
+                        0,
+                        format!("face {} has less than 3 vertices", instance),
+                    ));
+                }
+                if let Some(i) = list.iter().find(|&&i| i < 0.0 || i.fract() != 0.0) {
+                    let message = format!("'{}' is not a vertex index", i);
+                    return Err(match body.line() {
+                        0 => error(0, format!("face {}: {}", instance, message)),
+                        line => error(line, message),
+                    });
+                }
+                faces.push((instance, list.iter().map(|&i| i as usize).collect()));
+            }
+        }
+    }
+
+    // the vertices can come after the faces, the indices are checked once
+    // the whole file is read
+    let mut indices = Vec::new();
+    for (instance, list) in faces {
+        if list.iter().any(|&i| i >= positions.len()) {
+            return Err(error(0, format!("face {} uses a missing vertex", instance)));
+        }
+        // polygons are split in a fan of triangles around the first vertex
+        for i in 1..list.len() - 1 {
+            indices.push([list[0], list[i], list[i + 1]]);
+        }
+    }
+    if indices.is_empty() {
+        return Err(error(0, "the file has no faces".to_owned()));
+    }
+    let vertex_count = positions.len();
+    let mut mesh = TriangleMesh::new(positions, indices, material);
+    if normals.len() == vertex_count {
+        mesh = mesh.with_normals(normals.iter().map(|n| n.normalize()).collect());
+    }
+    if uvs.len() == vertex_count {
+        mesh = mesh.with_uvs(uvs);
+    }
+    if colors.len() == vertex_count {
+        mesh = mesh.with_colors(colors);
+    }
+    Ok(mesh)
+}
@@ -1,4 +1,5 @@
 use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::{thread, time};
 extern crate termsize;
 
@@ -56,3 +57,71 @@ pub fn progressbar(total_steps: usize, desc: &str) {
         green, desc, total_time, reset
     );
 }
+
+// a progress bar updated by the caller, e.g. from the rows of a render. it is
+// drawn on stderr and can be updated from many threads
+pub struct ProgressBar {
+    total: usize,
+    desc: String,
+    size: usize,
+    start_time: time::Instant,
+    // the last drawn progress, in tenths of a percent
+    drawn: AtomicUsize,
+}
+
+impl ProgressBar {
+    pub fn new(total: usize, desc: &str) -> Self {
+        let width = termsize::get().map_or(80, |size| usize::from(size.cols));
+        // room for the description, the percentage and the ETA
+        let size = width.saturating_sub(desc.len() + 40).clamp(10, 50);
+        let bar = Self {
+            total: total.max(1),
+            desc: desc.to_owned(),
+            size,
+            start_time: time::Instant::now(),
+            drawn: AtomicUsize::new(0),
+        };
+        bar.draw(0);
+        bar
+    }
+
+    pub fn set(&self, done: usize) {
+        let permille = done.min(self.total) * 1000 / self.total;
+        // only the thread that moves the bar forward draws it
+        if self.drawn.fetch_max(permille, Ordering::Relaxed) < permille {
+            self.draw(done);
+        }
+    }
+
+    fn draw(&self, done: usize) {
+        let done = done.min(self.total);
+        let filled = done * self.size / self.total;
+        let elapsed_time = self.start_time.elapsed().as_secs_f64();
+        let eta = if done > 0 {
+            let remaining_time = elapsed_time / done as f64 * (self.total - done) as f64;
+            format!("ETA: {:.1}s", remaining_time)
+        } else {
+            "".to_string()
+        };
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(
+            stderr,
+            "\r{}: [\x1B[32m{}{}\x1B[0m] {:.1}% ({})  ",
+            self.desc,
+            "█".repeat(filled),
+            "░".repeat(self.size - filled),
+            done as f64 * 100.0 / self.total as f64,
+            eta
+        );
+        let _ = stderr.flush();
+    }
+
+    pub fn finish(&self) {
+        self.draw(self.total);
+        eprintln!(
+            "\n{} completed in {:.1} seconds",
+            self.desc,
+            self.start_time.elapsed().as_secs_f64()
+        );
+    }
+}
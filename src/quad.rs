@@ -0,0 +1,125 @@
+use nalgebra::Vector3;
+
+use crate::aabb::AABB;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+
+/*
+* A parallelogram in any orientation: the points corner + a * u + b * v with
+* a and b between 0 and 1. The normal is u x v, so it points toward the side
+* where u turns counterclockwise into v. The uv of a hit are a and b
+*/
+pub struct Quad<M: Material> {
+    corner: Vector3<f64>,
+    u: Vector3<f64>,
+    v: Vector3<f64>,
+    normal: Vector3<f64>,
+    // (u x v) / |u x v|^2, its dot products with the cross products of a
+    // point give the coordinates a and b
+    w: Vector3<f64>,
+    area: f64,
+    material: M,
+}
+
+impl<M: Material> Quad<M> {
+    // panics if u and v are parallel
+    pub fn new(corner: Vector3<f64>, u: Vector3<f64>, v: Vector3<f64>, material: M) -> Self {
+        let n = u.cross(&v);
+        let area = n.norm();
+        assert!(area > 0.0, "the edges of a quad cannot be parallel");
+        Self {
+            corner,
+            u,
+            v,
+            normal: n / area,
+            w: n / (area * area),
+            area,
+            material,
+        }
+    }
+
+    pub fn normal(&self) -> &Vector3<f64> {
+        &self.normal
+    }
+
+    pub fn area(&self) -> f64 {
+        self.area
+    }
+}
+
+impl<M: Material> Hittable for Quad<M> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let denominator = self.normal.dot(&ray.direction());
+        if denominator.abs() < 1e-12 {
+            // the ray is parallel to the quad
+            return None;
+        }
+        let t = self.normal.dot(&(self.corner - ray.origin())) / denominator;
+        if t < t_min || t > t_max {
+            return None;
+        }
+        let p = ray.pointing_at(t);
+        let d = p - self.corner;
+        let a = self.w.dot(&d.cross(&self.v));
+        let b = self.w.dot(&self.u.cross(&d));
+        if !(0.0..=1.0).contains(&a) || !(0.0..=1.0).contains(&b) {
+            return None;
+        }
+        Some(HitRecord {
+            t,
+            u: a,
+            v: b,
+            p,
+            normal: self.normal,
+            material: &self.material,
+            vertex_color: None,
+        })
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<AABB> {
+        let corners = [
+            self.corner + self.u,
+            self.corner + self.v,
+            self.corner + self.u + self.v,
+        ];
+        let (mut min, mut max) = corners
+            .iter()
+            .fold((self.corner, self.corner), |(min, max), c| {
+                (min.inf(c), max.sup(c))
+            });
+        // a quad lying on an axis aligned plane still needs a thick box
+        for a in 0..3 {
+            if max[a] - min[a] < 0.0002 {
+                min[a] -= 0.0001;
+                max[a] += 0.0001;
+            }
+        }
+        Some(AABB { min, max })
+    }
+
+    // the points of the quad are sampled uniformly, an area of dA is seen
+    // from origin under a solid angle of dA * cos / distance^2
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Vector3<f64>) -> f64 {
+        match self.hit(&Ray::new(*origin, *direction, 0.0), 0.001, f64::MAX) {
+            Some(hit) => {
+                let distance2 = hit.t * hit.t * direction.magnitude_squared();
+                let cosine = (self.normal.dot(direction) / direction.magnitude()).abs();
+                distance2 / (cosine * self.area)
+            }
+            None => 0.0,
+        }
+    }
+
+    fn random(&self, origin: &Vector3<f64>, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        let (r1, r2) = sampler.next_2d();
+        self.corner + r1 * self.u + r2 * self.v - origin
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        if self.material.is_emissive() {
+            lights.push(self);
+        }
+    }
+}
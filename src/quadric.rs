@@ -0,0 +1,317 @@
+use nalgebra::Vector3;
+use std::f64;
+
+use crate::aabb::AABB;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::util::quadratic_roots;
+
+// the angle of a point around the y axis in [0, 2 pi), counterclockwise seen
+// from above starting at the x axis
+fn azimuth(x: f64, z: f64) -> f64 {
+    let phi = (-z).atan2(x);
+    if phi < 0.0 {
+        phi + 2.0 * f64::consts::PI
+    } else {
+        phi
+    }
+}
+
+// the box of a part of a circle around the y axis between two heights: the
+// two ends of the sweep, the axis and the directions of the axes it passes
+fn sweep_box(radius: f64, sweep: f64, y0: f64, y1: f64) -> AABB {
+    let mut min = Vector3::new(0.0, y0, 0.0);
+    let mut max = Vector3::new(0.0, y1, 0.0);
+    let mut angles = vec![0.0, sweep];
+    angles.extend(
+        [0.5, 1.0, 1.5]
+            .iter()
+            .map(|k| k * f64::consts::PI)
+            .filter(|&phi| phi < sweep),
+    );
+    for phi in angles {
+        let (x, z) = (radius * phi.cos(), -radius * phi.sin());
+        min.x = min.x.min(x);
+        max.x = max.x.max(x);
+        min.z = min.z.min(z);
+        max.z = max.z.max(z);
+    }
+    AABB::new(min, max)
+}
+
+// a hit in the space of the shape, before it is moved to its position
+struct SurfaceHit {
+    t: f64,
+    normal: Vector3<f64>,
+    u: f64,
+    v: f64,
+}
+
+// the cap of a shape at height y, a part of a disk with the same sweep. the
+// uv are polar like the ones of a disk
+fn cap(
+    ray: &Ray,
+    y: f64,
+    normal_y: f64,
+    radius: f64,
+    sweep: f64,
+    t_min: f64,
+    t_max: f64,
+) -> Option<SurfaceHit> {
+    if ray.direction().y == 0.0 {
+        return None;
+    }
+    let t = (y - ray.origin().y) / ray.direction().y;
+    if t <= t_min || t >= t_max {
+        return None;
+    }
+    let p = ray.pointing_at(t);
+    let distance = (p.x * p.x + p.z * p.z).sqrt();
+    let phi = azimuth(p.x, p.z);
+    if distance > radius || phi > sweep {
+        return None;
+    }
+    Some(SurfaceHit {
+        t,
+        normal: Vector3::new(0.0, normal_y, 0.0),
+        u: phi / sweep,
+        v: distance / radius,
+    })
+}
+
+fn sweep_radians(degrees: f64) -> f64 {
+    degrees.clamp(0.0, 360.0).to_radians()
+}
+
+/*
+* A cylinder standing on the y axis: the base is centered at center, the top
+* is height above it. The caps close both ends unless uncapped, a sweep below
+* 360 degrees keeps only the part between the x axis and the angle
+* (counterclockwise seen from above), the cut is left open. On the side u goes
+* around the axis and v goes up, the caps have the polar uv of a disk. Other
+* orientations are a transform away. Like the triangles, they are not sampled
+* as lights
+*/
+pub struct Cylinder<M: Material> {
+    center: Vector3<f64>,
+    radius: f64,
+    height: f64,
+    capped: bool,
+    sweep: f64,
+    material: M,
+}
+
+impl<M: Material> Cylinder<M> {
+    pub fn new(center: Vector3<f64>, radius: f64, height: f64, material: M) -> Self {
+        Self {
+            center,
+            radius,
+            height,
+            capped: true,
+            sweep: 2.0 * f64::consts::PI,
+            material,
+        }
+    }
+
+    pub fn uncapped(mut self) -> Self {
+        self.capped = false;
+        self
+    }
+
+    // in degrees, between 0 and 360
+    pub fn with_sweep(mut self, sweep: f64) -> Self {
+        self.sweep = sweep_radians(sweep);
+        self
+    }
+}
+
+impl<M: Material> Hittable for Cylinder<M> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        // the ray in the space of the shape, the base at the origin
+        let local = Ray::new(ray.origin() - self.center, ray.direction(), ray.time());
+        let (origin, direction) = (local.origin(), local.direction());
+        let mut closest_so_far = t_max;
+        let mut hit_anything = None;
+
+        let a = direction.x * direction.x + direction.z * direction.z;
+        let half_b = origin.x * direction.x + origin.z * direction.z;
+        let c = origin.x * origin.x + origin.z * origin.z - self.radius * self.radius;
+        if let Some((t0, t1)) = quadratic_roots(a, half_b, c) {
+            for &t in [t0, t1].iter() {
+                if t <= t_min || t >= closest_so_far {
+                    continue;
+                }
+                let p = origin + t * direction;
+                let phi = azimuth(p.x, p.z);
+                if p.y < 0.0 || p.y > self.height || phi > self.sweep {
+                    continue;
+                }
+                closest_so_far = t;
+                hit_anything = Some(SurfaceHit {
+                    t,
+                    normal: Vector3::new(p.x, 0.0, p.z) / self.radius,
+                    u: phi / self.sweep,
+                    v: p.y / self.height,
+                });
+                break;
+            }
+        }
+
+        if self.capped {
+            for &(y, normal_y) in [(0.0, -1.0), (self.height, 1.0)].iter() {
+                if let Some(hit) = cap(
+                    &local,
+                    y,
+                    normal_y,
+                    self.radius,
+                    self.sweep,
+                    t_min,
+                    closest_so_far,
+                ) {
+                    closest_so_far = hit.t;
+                    hit_anything = Some(hit);
+                }
+            }
+        }
+
+        hit_anything.map(|hit| HitRecord {
+            t: hit.t,
+            u: hit.u,
+            v: hit.v,
+            p: ray.pointing_at(hit.t),
+            normal: hit.normal,
+            material: &self.material,
+            vertex_color: None,
+        })
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<AABB> {
+        let bbox = sweep_box(self.radius, self.sweep, 0.0, self.height);
+        Some(AABB::new(bbox.min + self.center, bbox.max + self.center))
+    }
+}
+
+/*
+* A cone on the y axis: the base of the given radius is centered at center,
+* the apex is height above it. The cap, the sweep and the uv are the ones of
+* the cylinder
+*/
+pub struct Cone<M: Material> {
+    center: Vector3<f64>,
+    radius: f64,
+    height: f64,
+    capped: bool,
+    sweep: f64,
+    material: M,
+}
+
+impl<M: Material> Cone<M> {
+    pub fn new(center: Vector3<f64>, radius: f64, height: f64, material: M) -> Self {
+        Self {
+            center,
+            radius,
+            height,
+            capped: true,
+            sweep: 2.0 * f64::consts::PI,
+            material,
+        }
+    }
+
+    pub fn uncapped(mut self) -> Self {
+        self.capped = false;
+        self
+    }
+
+    // in degrees, between 0 and 360
+    pub fn with_sweep(mut self, sweep: f64) -> Self {
+        self.sweep = sweep_radians(sweep);
+        self
+    }
+}
+
+impl<M: Material> Hittable for Cone<M> {
+    // the side is x^2 + z^2 = k^2 (height - y)^2 with k = radius / height,
+    // below the apex
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        // the ray in the space of the shape, the base at the origin
+        let local = Ray::new(ray.origin() - self.center, ray.direction(), ray.time());
+        let (origin, direction) = (local.origin(), local.direction());
+        let mut closest_so_far = t_max;
+        let mut hit_anything = None;
+
+        let k2 = (self.radius / self.height).powi(2);
+        let h = self.height - origin.y;
+        let a =
+            direction.x * direction.x + direction.z * direction.z - k2 * direction.y * direction.y;
+        let half_b = origin.x * direction.x + origin.z * direction.z + k2 * h * direction.y;
+        let c = origin.x * origin.x + origin.z * origin.z - k2 * h * h;
+        let roots = if a.abs() < 1e-12 {
+            // parallel to the side, only one root
+            if half_b == 0.0 {
+                None
+            } else {
+                let t = -c / (2.0 * half_b);
+                Some((t, t))
+            }
+        } else {
+            quadratic_roots(a, half_b, c)
+        };
+        if let Some((t0, t1)) = roots {
+            for &t in [t0, t1].iter() {
+                if t <= t_min || t >= closest_so_far {
+                    continue;
+                }
+                let p = origin + t * direction;
+                let phi = azimuth(p.x, p.z);
+                if p.y < 0.0 || p.y > self.height || phi > self.sweep {
+                    continue;
+                }
+                // the gradient of the equation, the apex takes the axis
+                let normal = Vector3::new(p.x, k2 * (self.height - p.y), p.z);
+                let normal = if normal.norm() > 0.0 {
+                    normal.normalize()
+                } else {
+                    Vector3::new(0.0, 1.0, 0.0)
+                };
+                closest_so_far = t;
+                hit_anything = Some(SurfaceHit {
+                    t,
+                    normal,
+                    u: phi / self.sweep,
+                    v: p.y / self.height,
+                });
+                break;
+            }
+        }
+
+        if self.capped {
+            if let Some(hit) = cap(
+                &local,
+                0.0,
+                -1.0,
+                self.radius,
+                self.sweep,
+                t_min,
+                closest_so_far,
+            ) {
+                hit_anything = Some(hit);
+            }
+        }
+
+        hit_anything.map(|hit| HitRecord {
+            t: hit.t,
+            u: hit.u,
+            v: hit.v,
+            p: ray.pointing_at(hit.t),
+            normal: hit.normal,
+            material: &self.material,
+            vertex_color: None,
+        })
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<AABB> {
+        let bbox = sweep_box(self.radius, self.sweep, 0.0, self.height);
+        Some(AABB::new(bbox.min + self.center, bbox.max + self.center))
+    }
+}
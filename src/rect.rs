@@ -4,6 +4,7 @@ use crate::aabb::AABB;
 use crate::hittable::{HitRecord, Hittable};
 use crate::material::Material;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 
 pub enum Plane {
     YZ,
@@ -11,6 +12,17 @@ pub enum Plane {
     XY,
 }
 
+impl Plane {
+    // the axis orthogonal to the plane followed by the two axes of the rect
+    fn axes(&self) -> (usize, usize, usize) {
+        match self {
+            Plane::YZ => (0, 1, 2),
+            Plane::ZX => (1, 2, 0),
+            Plane::XY => (2, 0, 1),
+        }
+    }
+}
+
 pub struct Rect<M: Material> {
     plane: Plane,
     x0: f64,
@@ -37,11 +49,7 @@ impl<M: Material> Rect<M> {
 
 impl<M: Material> Hittable for Rect<M> {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let (k_axis, a_axis, b_axis) = match &&self.plane {
-            Plane::YZ => (0, 1, 2),
-            Plane::ZX => (1, 2, 0),
-            Plane::XY => (2, 0, 1),
-        };
+        let (k_axis, a_axis, b_axis) = self.plane.axes();
         let t = (self.k - ray.origin()[k_axis]) / ray.direction()[k_axis];
         if t < t_min || t > t_max {
             None
@@ -63,14 +71,53 @@ impl<M: Material> Hittable for Rect<M> {
                     p,
                     normal,
                     material: &self.material,
+                    vertex_color: None,
                 })
             }
         }
     }
 
     fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<AABB> {
-        let min = Vector3::new(self.x0, self.y0, self.k - 0.0001);
-        let max = Vector3::new(self.x1, self.y1, self.k + 0.0001);
+        let (k_axis, a_axis, b_axis) = self.plane.axes();
+        let mut min = Vector3::zeros();
+        let mut max = Vector3::zeros();
+        min[a_axis] = self.x0;
+        min[b_axis] = self.y0;
+        min[k_axis] = self.k - 0.0001;
+        max[a_axis] = self.x1;
+        max[b_axis] = self.y1;
+        max[k_axis] = self.k + 0.0001;
         Some(AABB { min, max })
     }
+
+    // the points of the rect are sampled uniformly, an area of dA is seen
+    // from origin under a solid angle of dA * cos / distance^2
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Vector3<f64>) -> f64 {
+        match self.hit(&Ray::new(*origin, *direction, 0.0), 0.001, f64::MAX) {
+            Some(hit) => {
+                let (k_axis, _, _) = self.plane.axes();
+                let area = (self.x1 - self.x0) * (self.y1 - self.y0);
+                let distance2 = hit.t * hit.t * direction.magnitude_squared();
+                let cosine = (direction[k_axis] / direction.magnitude()).abs();
+                distance2 / (cosine * area)
+            }
+            None => 0.0,
+        }
+    }
+
+    fn random(&self, origin: &Vector3<f64>, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        let (k_axis, a_axis, b_axis) = self.plane.axes();
+        let (r1, r2) = sampler.next_2d();
+        let mut point = Vector3::zeros();
+        point[a_axis] = self.x0 + r1 * (self.x1 - self.x0);
+        point[b_axis] = self.y0 + r2 * (self.y1 - self.y0);
+        point[k_axis] = self.k;
+        point - origin
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        if self.material.is_emissive() {
+            lights.push(self);
+        }
+    }
 }
@@ -0,0 +1,200 @@
+use nalgebra::Vector3;
+use rand::Rng;
+use rayon::prelude::*;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::background::Background;
+use crate::camera::Camera;
+use crate::color::{color, PathDepth};
+use crate::hittable::Hittable;
+use crate::sampler::SamplerType;
+use crate::tonemap::ToneMapping;
+
+#[derive(Clone, Copy, Debug)]
+pub struct RenderSettings {
+    pub width: usize,
+    pub height: usize,
+    pub samples: usize,
+    pub depth: PathDepth,
+    // the color of the rays that leave the scene, unless the renderer has a
+    // background of its own
+    pub background: Vector3<f64>,
+    // every random number of a render follows from the seed, two renders
+    // with the same seed are identical. a random seed when None
+    pub seed: Option<u64>,
+    // how the random numbers of the samples are spread
+    pub sampler: SamplerType,
+    // used when the frame is saved in a display format
+    pub tone_mapping: ToneMapping,
+}
+
+impl RenderSettings {
+    pub fn aspect_ratio(&self) -> f64 {
+        self.width as f64 / self.height as f64
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            width: 1280,
+            height: 720,
+            samples: 100,
+            depth: PathDepth::default(),
+            background: Vector3::zeros(),
+            seed: None,
+            sampler: SamplerType::default(),
+            tone_mapping: ToneMapping::default(),
+        }
+    }
+}
+
+// linear radiance of every pixel, stored row by row starting from the top
+// of the image (the same order used by the image formats)
+#[derive(Clone, Debug)]
+pub struct FrameBuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vector3<f64>>,
+}
+
+impl FrameBuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![Vector3::zeros(); width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[Vector3<f64>] {
+        &self.pixels
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Vector3<f64> {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Vector3<f64>) {
+        self.pixels[y * self.width + x] = color;
+    }
+
+    // plain text P3 image of the tone mapped colors
+    pub fn to_ppm(&self, tone_mapping: &ToneMapping) -> String {
+        let mut output = String::new();
+        writeln!(output, "P3\n{} {}\n255", self.width, self.height).unwrap();
+        for pixel in self.pixels.iter() {
+            let col = tone_mapping.apply(pixel);
+            let ir = (255.0 * col[0]).round() as i32;
+            let ig = (255.0 * col[1]).round() as i32;
+            let ib = (255.0 * col[2]).round() as i32;
+            writeln!(output, "{} {} {}", ir, ig, ib).unwrap();
+        }
+        output
+    }
+}
+
+pub struct Renderer {
+    settings: RenderSettings,
+    background: Option<Arc<dyn Background>>,
+}
+
+impl Renderer {
+    pub fn new(settings: RenderSettings) -> Self {
+        Self {
+            settings,
+            background: None,
+        }
+    }
+
+    // a background that changes with the direction, e.g. an environment map
+    pub fn with_background(settings: RenderSettings, background: Arc<dyn Background>) -> Self {
+        Self {
+            settings,
+            background: Some(background),
+        }
+    }
+
+    pub fn settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+
+    pub fn render(&self, camera: &Camera, world: &dyn Hittable) -> FrameBuffer {
+        self.render_with_progress(camera, world, |_| {})
+    }
+
+    // every row of the image is rendered by its own rayon task, the rows are
+    // independent so the threads never need to synchronize. progress is called
+    // with the number of finished rows every time a row is done, from any thread
+    pub fn render_with_progress<F>(
+        &self,
+        camera: &Camera,
+        world: &dyn Hittable,
+        progress: F,
+    ) -> FrameBuffer
+    where
+        F: Fn(usize) + Sync,
+    {
+        let nx = self.settings.width;
+        let ny = self.settings.height;
+        let ns = self.settings.samples.max(1);
+        let mut frame = FrameBuffer::new(nx, ny);
+        let finished = AtomicUsize::new(0);
+        let mut lights = Vec::new();
+        world.collect_lights(&mut lights);
+        let background: &dyn Background = match &self.background {
+            Some(background) => background.as_ref(),
+            None => &self.settings.background,
+        };
+        let seed = self
+            .settings
+            .seed
+            .unwrap_or_else(|| rand::thread_rng().gen());
+
+        frame
+            .pixels
+            .par_chunks_mut(nx.max(1))
+            .enumerate()
+            .for_each(|(row, pixels)| {
+                // the sampler is restarted for every sample, whatever thread
+                // renders the row
+                let mut sampler = self.settings.sampler.build(seed, ns);
+                // the camera has v pointing up, the frame buffer starts from the top
+                let j = ny - 1 - row;
+                for (i, pixel) in pixels.iter_mut().enumerate() {
+                    let mut col = Vector3::new(0.0, 0.0, 0.0);
+                    for s in 0..ns {
+                        // the first dimensions are the position in the pixel,
+                        // then the lens and the time of the camera, then the
+                        // bounces of the path
+                        sampler.start_sample(i, j, s);
+                        let (du, dv) = sampler.next_2d();
+                        let u = (i as f64 + du) / nx as f64;
+                        let v = (j as f64 + dv) / ny as f64;
+                        let ray = camera.get_ray(u, v, sampler.as_mut());
+                        col += color(
+                            &ray,
+                            world,
+                            &lights,
+                            background,
+                            &self.settings.depth,
+                            sampler.as_mut(),
+                        );
+                    }
+                    *pixel = col / ns as f64;
+                }
+                progress(finished.fetch_add(1, Ordering::Relaxed) + 1);
+            });
+        frame
+    }
+}
@@ -1,6 +1,7 @@
 use crate::aabb::AABB;
-use crate::hittable::{HitRecord, Hittable};
+use crate::hittable::{self, HitRecord, Hittable};
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use nalgebra::Vector3;
 use std::f64;
 
@@ -79,35 +80,35 @@ impl<H: Hittable> Rotate<H> {
             bbox,
         }
     }
+
+    // from the world to the space of the rotated hittable
+    fn to_object(&self, v: &Vector3<f64>) -> Vector3<f64> {
+        let (_, a_axis, b_axis) = get_axis(&self.axis);
+        let mut r = *v;
+        r[a_axis] = self.cos_theta * v[a_axis] + self.sin_theta * v[b_axis];
+        r[b_axis] = -self.sin_theta * v[a_axis] + self.cos_theta * v[b_axis];
+        r
+    }
+
+    fn to_world(&self, v: &Vector3<f64>) -> Vector3<f64> {
+        let (_, a_axis, b_axis) = get_axis(&self.axis);
+        let mut r = *v;
+        r[a_axis] = self.cos_theta * v[a_axis] - self.sin_theta * v[b_axis];
+        r[b_axis] = self.sin_theta * v[a_axis] + self.cos_theta * v[b_axis];
+        r
+    }
 }
 
 impl<H: Hittable> Hittable for Rotate<H> {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let (_, a_axis, b_axis) = get_axis(&self.axis);
-        let mut origin = ray.origin();
-        let mut direction = ray.direction();
-        origin[a_axis] =
-            self.cos_theta * ray.origin()[a_axis] + self.sin_theta * ray.origin()[b_axis];
-        origin[b_axis] =
-            -self.sin_theta * ray.origin()[a_axis] + self.cos_theta * ray.origin()[b_axis];
-        direction[a_axis] =
-            self.cos_theta * ray.direction()[a_axis] + self.sin_theta * ray.direction()[b_axis];
-        direction[b_axis] =
-            -self.sin_theta * ray.direction()[a_axis] + self.cos_theta * ray.direction()[b_axis];
+        let origin = self.to_object(&ray.origin());
+        let direction = self.to_object(&ray.direction());
         let rotated_ray = Ray::new(origin, direction, ray.time());
         self.hittable
             .hit(&rotated_ray, t_min, t_max)
             .map(|mut hit| {
-                let mut p = hit.p;
-                let mut normal = hit.normal;
-                p[a_axis] = self.cos_theta * hit.p[a_axis] - self.sin_theta * hit.p[b_axis];
-                p[b_axis] = self.sin_theta * hit.p[a_axis] + self.cos_theta * hit.p[b_axis];
-                normal[a_axis] =
-                    self.cos_theta * hit.normal[a_axis] - self.sin_theta * hit.normal[b_axis];
-                normal[b_axis] =
-                    self.sin_theta * hit.normal[a_axis] + self.cos_theta * hit.normal[b_axis];
-                hit.p = p;
-                hit.normal = normal;
+                hit.p = self.to_world(&hit.p);
+                hit.normal = self.to_world(&hit.normal);
                 hit
             })
     }
@@ -115,4 +116,20 @@ impl<H: Hittable> Hittable for Rotate<H> {
     fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<AABB> {
         self.bbox.clone()
     }
+
+    // a rotation keeps the solid angles
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Vector3<f64>) -> f64 {
+        self.hittable
+            .pdf_value(&self.to_object(origin), &self.to_object(direction))
+    }
+
+    fn random(&self, origin: &Vector3<f64>, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        self.to_world(&self.hittable.random(&self.to_object(origin), sampler))
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        if hittable::has_lights(&self.hittable) {
+            lights.push(self);
+        }
+    }
 }
@@ -0,0 +1,341 @@
+/*
+* The source of every random number of a render. The renderer restarts the
+* sampler for each sample of each pixel, so the numbers depend only on the
+* seed, the pixel and the sample, never on the thread that renders it, and
+* two renders with the same seed are identical
+*/
+pub trait Sampler {
+    // called before the sample `index` of the pixel (x, y)
+    fn start_sample(&mut self, x: usize, y: usize, index: usize);
+
+    // a uniform number in [0, 1)
+    fn next_1d(&mut self) -> f64;
+
+    fn next_2d(&mut self) -> (f64, f64) {
+        (self.next_1d(), self.next_1d())
+    }
+}
+
+// the finalizer of splitmix64, every bit of the input changes half of the
+// bits of the output
+pub fn mix(x: u64) -> u64 {
+    let mut z = x;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+pub fn hash(values: &[u64]) -> u64 {
+    values
+        .iter()
+        .fold(0x2545_F491_4F6C_DD1D, |h, &v| mix(h ^ mix(v)))
+}
+
+// the 53 high bits of a hash as a number in [0, 1)
+pub fn to_unit(bits: u64) -> f64 {
+    (bits >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+// independent uniform numbers from a splitmix64 stream seeded with a hash of
+// the seed, the pixel and the sample
+#[derive(Clone, Debug)]
+pub struct IndependentSampler {
+    seed: u64,
+    state: u64,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            state: mix(seed),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.state = hash(&[self.seed, x as u64, y as u64, index as u64]);
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        to_unit(mix(self.state))
+    }
+}
+
+// jittered strata: the samples of a pixel fall in different strata of every
+// dimension, each dimension visits the strata in its own random order.
+// 2D draws use a grid of strata
+#[derive(Clone, Debug)]
+pub struct StratifiedSampler {
+    seed: u64,
+    samples: usize,
+    pixel: u64,
+    index: usize,
+    dimension: u64,
+}
+
+impl StratifiedSampler {
+    // samples is the number of samples of every pixel, the strata it is split in
+    pub fn new(seed: u64, samples: usize) -> Self {
+        Self {
+            seed,
+            samples: samples.max(1),
+            pixel: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    // the stratum of the sample in the next dimension and random bits for
+    // the jitter inside it
+    fn next_stratum(&mut self, strata: usize) -> (usize, u64) {
+        let dimension_hash = hash(&[self.pixel, self.dimension]);
+        let stratum = permutation_element(
+            (self.index % strata) as u32,
+            strata as u32,
+            dimension_hash as u32,
+        ) as usize;
+        self.dimension += 1;
+        (stratum, hash(&[dimension_hash, self.index as u64]))
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.pixel = hash(&[self.seed, x as u64, y as u64]);
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        let (stratum, bits) = self.next_stratum(self.samples);
+        (stratum as f64 + to_unit(bits)) / self.samples as f64
+    }
+
+    // the grid is the smallest one with a cell for every sample
+    fn next_2d(&mut self) -> (f64, f64) {
+        let nx = (self.samples as f64).sqrt().ceil() as usize;
+        let ny = self.samples.div_ceil(nx);
+        let (stratum, bits) = self.next_stratum(nx * ny);
+        (
+            ((stratum % nx) as f64 + to_unit(bits)) / nx as f64,
+            ((stratum / nx) as f64 + to_unit(mix(bits))) / ny as f64,
+        )
+    }
+}
+
+// element i of a random permutation of 0..n picked by seed, without building
+// the permutation (Kensler, Correlated Multi-Jittered Sampling)
+fn permutation_element(mut i: u32, n: u32, seed: u32) -> u32 {
+    let mut w = n - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= seed >> 16;
+        i ^= (i & w) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= seed >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < n {
+            return i.wrapping_add(seed) % n;
+        }
+    }
+}
+
+const PRIMES: [u64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+// the Halton sequence, dimension d is the radical inverse in the base of the
+// d-th prime. the digits are Owen scrambled with a seed of every pixel and
+// dimension, so the pixels do not repeat the same pattern and the large
+// bases do not make lines of points. the dimensions past the table of primes
+// are independent numbers
+#[derive(Clone, Debug)]
+pub struct HaltonSampler {
+    seed: u64,
+    pixel: u64,
+    index: u64,
+    dimension: usize,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            pixel: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+}
+
+// the digits of index in base, mirrored around the point. every digit is
+// permuted with a permutation picked by the digits before it, up to the
+// precision of an f64 (the leading zeros are digits too)
+fn scrambled_radical_inverse(base: u64, mut index: u64, seed: u64) -> f64 {
+    let inverse_base = 1.0 / base as f64;
+    let (mut value, mut scale) = (0.0, inverse_base);
+    // the digits before the current one, wraps for the large bases
+    let mut prefix = 0u64;
+    while 1.0 - (base - 1) as f64 * scale < 1.0 {
+        let digit_seed = mix(seed ^ prefix) as u32;
+        let digit = permutation_element((index % base) as u32, base as u32, digit_seed);
+        value += digit as f64 * scale;
+        prefix = prefix.wrapping_mul(base).wrapping_add(digit as u64);
+        index /= base;
+        scale *= inverse_base;
+    }
+    value.min(1.0 - f64::EPSILON / 2.0)
+}
+
+impl Sampler for HaltonSampler {
+    fn start_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.pixel = hash(&[self.seed, x as u64, y as u64]);
+        self.index = index as u64;
+        self.dimension = 0;
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        let seed = hash(&[self.pixel, self.dimension as u64]);
+        let value = match PRIMES.get(self.dimension) {
+            Some(&base) => scrambled_radical_inverse(base, self.index, seed),
+            None => to_unit(hash(&[seed, self.index])),
+        };
+        self.dimension += 1;
+        value
+    }
+}
+
+// Owen-scrambled Sobol points (Burley, Practical Hash-based Owen Scrambling).
+// every draw takes the first one or two dimensions of the Sobol sequence,
+// which are well stratified together, and scrambles them with a seed of its
+// own; the sample index is shuffled the same way, so the draws are not
+// correlated with each other
+#[derive(Clone, Debug)]
+pub struct SobolSampler {
+    seed: u64,
+    pixel: u64,
+    index: u32,
+    dimension: u64,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            pixel: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    // the scrambled index of the sample and the seed of the next draw
+    fn next_draw(&mut self) -> (u32, u64) {
+        let draw = hash(&[self.pixel, self.dimension]);
+        self.dimension += 1;
+        (nested_uniform_scramble(self.index, draw as u32), draw)
+    }
+}
+
+// the first two dimensions of the Sobol sequence: the van der Corput sequence
+// and the one with the direction numbers of the polynomial x + 1
+fn sobol(index: u32, dimension: usize) -> u32 {
+    let (mut x, mut v, mut i) = (0, 1u32 << 31, index);
+    while i != 0 {
+        if i & 1 != 0 {
+            x ^= v;
+        }
+        i >>= 1;
+        v = if dimension == 0 { v >> 1 } else { v ^ (v >> 1) };
+    }
+    x
+}
+
+// a random permutation of the bits where every bit only depends on the
+// higher ones, which keeps the stratification of the points
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    let mut x = x.reverse_bits().wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50_b47c);
+    x ^= x.wrapping_mul(0xb82f_1e52);
+    x ^= x.wrapping_mul(0xc7af_e638);
+    x ^= x.wrapping_mul(0x8d22_f6e6);
+    x.reverse_bits()
+}
+
+fn u32_to_unit(x: u32) -> f64 {
+    x as f64 / 4_294_967_296.0
+}
+
+impl Sampler for SobolSampler {
+    fn start_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.pixel = hash(&[self.seed, x as u64, y as u64]);
+        self.index = index as u32;
+        self.dimension = 0;
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        let (index, draw) = self.next_draw();
+        u32_to_unit(nested_uniform_scramble(sobol(index, 0), mix(draw) as u32))
+    }
+
+    fn next_2d(&mut self) -> (f64, f64) {
+        let (index, draw) = self.next_draw();
+        let x = nested_uniform_scramble(sobol(index, 0), mix(draw) as u32);
+        let y = nested_uniform_scramble(sobol(index, 1), mix(draw ^ 1) as u32);
+        (u32_to_unit(x), u32_to_unit(y))
+    }
+}
+
+// the samplers that can be picked for a render
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SamplerType {
+    Independent,
+    Stratified,
+    Halton,
+    #[default]
+    Sobol,
+}
+
+impl SamplerType {
+    pub const NAMES: [&'static str; 4] = ["independent", "stratified", "halton", "sobol"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "independent" => Some(SamplerType::Independent),
+            "stratified" => Some(SamplerType::Stratified),
+            "halton" => Some(SamplerType::Halton),
+            "sobol" => Some(SamplerType::Sobol),
+            _ => None,
+        }
+    }
+
+    // samples is the number of samples of every pixel
+    pub fn build(self, seed: u64, samples: usize) -> Box<dyn Sampler> {
+        match self {
+            SamplerType::Independent => Box::new(IndependentSampler::new(seed)),
+            SamplerType::Stratified => Box::new(StratifiedSampler::new(seed, samples)),
+            SamplerType::Halton => Box::new(HaltonSampler::new(seed)),
+            SamplerType::Sobol => Box::new(SobolSampler::new(seed)),
+        }
+    }
+}
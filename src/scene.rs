@@ -0,0 +1,1136 @@
+use nalgebra::{Matrix4, Unit, UnitQuaternion, Vector3};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::background::{Background, EnvironmentMap, Gradient};
+use crate::bvh::BVHNode;
+use crate::camera::{Camera, CameraSettings};
+use crate::capsule::Capsule;
+use crate::cube::Cube;
+use crate::disk::Disk;
+use crate::hittable::{FlipNormals, Hittable, HittableList};
+use crate::instance::Instance;
+use crate::material::{Dielectric, DiffuseLight, Isotropic, Lambertian, Material, Metal};
+use crate::medium::ConstantMedium;
+use crate::plane::InfinitePlane;
+use crate::quad::Quad;
+use crate::quadric::{Cone, Cylinder};
+use crate::rect::{Plane, Rect};
+use crate::renderer::{RenderSettings, Renderer};
+use crate::rotate::{Axis, Rotate};
+use crate::sampler::SamplerType;
+use crate::sphere::{MovingSphere, Sphere};
+use crate::texture::{CheckerTexture, ImageTexture, NoiseTexture, SolidTexture, Texture};
+use crate::tonemap::ToneMapOperator;
+use crate::torus::Torus;
+use crate::transform::{self, AnimatedTransform, Keyframe, Transform};
+use crate::traslate::Traslate;
+
+/*
+* Scene description files. A scene is a list of blocks, every block is a
+* keyword followed by its fields between braces:
+*
+*   settings { width 800 height 800 samples 100 max_depth 50 tonemap aces }
+*   background environment { file "sky.hdr" intensity 1.5 rotation 90 }
+*   camera { look_from 278 278 -800 look_at 278 278 0 fov 40 }
+*   texture checker checker { odd 0.2 0.3 0.1 even 0.9 0.9 0.9 }
+*   material white lambertian { albedo 0.73 0.73 0.73 }
+*   sphere { center 0 1 0 radius 1 material white }
+*   quad { corner 0 0 0 u 1 0 0 v 0 1 1 material white }
+*   plane { point 0 0 0 normal 0 1 0 material white }
+*   cylinder { center 0 0 0 radius 1 height 2 sweep 270 uncapped material white }
+*   translate { offset 130 0 65 rotate { axis y angle -18 cube { ... } } }
+*   transform { scale 2 1 1 rotate 1 1 0 30 translate 0 1 0 sphere { ... } }
+*   animate { key { time 0 } key { time 1 rotate 0 1 0 90 } cube { ... } }
+*   prototype tree { bvh { ... } }
+*   instance { prototype tree translate 10 0 5 material bark }
+*
+* a texture is three numbers (a solid color) or the name of a texture, a
+* material is always a name. `#` starts a comment. besides max_depth the
+* settings take min_depth (bounces before russian roulette) and the limits
+* diffuse_depth, specular_depth, transmission_depth and volume_depth, all of
+* them can be 0 (a depth of 0 turns the lobe off), seed
+* to make the renders repeatable and sampler (independent, stratified, halton
+* or sobol).
+* the background is constant { color }, gradient { bottom top } (the sky of
+* the book without fields) or environment { file intensity rotation }.
+* a disk takes center, normal and radius. a plane has no borders, inside a
+* bvh it is tested apart from the other objects. a cone has the fields of a
+* cylinder (sweep and uncapped can be left out), a capsule takes start, end
+* and radius, a torus center, major and minor.
+* a prototype is drawn only by its instances, the material of an instance
+* replaces the ones of the prototype and can be left out, it cannot be
+* emissive
+*/
+#[derive(Debug)]
+pub struct SceneError {
+    pub path: PathBuf,
+    // 0 when the error is not about a specific position (e.g. a missing file)
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line > 0 {
+            write!(
+                f,
+                "{}:{}:{}: {}",
+                self.path.display(),
+                self.line,
+                self.column,
+                self.message
+            )
+        } else {
+            write!(f, "{}: {}", self.path.display(), self.message)
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+pub struct Scene {
+    pub world: Box<dyn Hittable>,
+    pub camera: CameraSettings,
+    pub settings: RenderSettings,
+    // when None the rays that leave the scene get the background color of
+    // the settings
+    pub background: Option<Arc<dyn Background>>,
+}
+
+impl Scene {
+    // the camera for the image size of the render settings
+    pub fn camera(&self) -> Camera {
+        self.camera.build(self.settings.aspect_ratio())
+    }
+
+    // a renderer for the settings and the background of the scene
+    pub fn renderer(&self) -> Renderer {
+        match &self.background {
+            Some(background) => Renderer::with_background(self.settings, background.clone()),
+            None => Renderer::new(self.settings),
+        }
+    }
+}
+
+#[derive(PartialEq)]
+enum TokenKind {
+    Word,
+    Quoted,
+    Open,
+    Close,
+}
+
+struct Token {
+    kind: TokenKind,
+    text: String,
+    line: usize,
+    column: usize,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, (usize, usize, String)> {
+    let mut tokens = Vec::new();
+    for (n, line) in source.lines().enumerate() {
+        let mut chars = line.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            let (line, column) = (n + 1, line[..i].chars().count() + 1);
+            let token = |kind, text: String| Token {
+                kind,
+                text,
+                line,
+                column,
+            };
+            match c {
+                '#' => break,
+                '{' => tokens.push(token(TokenKind::Open, c.to_string())),
+                '}' => tokens.push(token(TokenKind::Close, c.to_string())),
+                '"' => {
+                    let mut text = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, '"')) => break,
+                            Some((_, c)) => text.push(c),
+                            None => return Err((line, column, "unterminated string".to_owned())),
+                        }
+                    }
+                    tokens.push(token(TokenKind::Quoted, text));
+                }
+                c if c.is_whitespace() => {}
+                c => {
+                    let mut text = c.to_string();
+                    while let Some(&(_, c)) = chars.peek() {
+                        if c.is_whitespace() || c == '{' || c == '}' || c == '"' || c == '#' {
+                            break;
+                        }
+                        text.push(c);
+                        chars.next();
+                    }
+                    tokens.push(token(TokenKind::Word, text));
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+const OBJECTS: [&str; 19] = [
+    "sphere",
+    "moving_sphere",
+    "rect",
+    "quad",
+    "disk",
+    "plane",
+    "cylinder",
+    "cone",
+    "capsule",
+    "torus",
+    "cube",
+    "medium",
+    "rotate",
+    "translate",
+    "transform",
+    "animate",
+    "instance",
+    "flip",
+    "bvh",
+];
+
+struct Parser<'a> {
+    path: &'a Path,
+    // image files are relative to the directory of the scene
+    directory: PathBuf,
+    tokens: Vec<Token>,
+    next: usize,
+    textures: HashMap<String, Arc<dyn Texture>>,
+    materials: HashMap<String, Arc<dyn Material>>,
+    prototypes: HashMap<String, Arc<dyn Hittable>>,
+}
+
+impl<'a> Parser<'a> {
+    fn error_at(&self, token: &Token, message: String) -> SceneError {
+        SceneError {
+            path: self.path.to_owned(),
+            line: token.line,
+            column: token.column,
+            message,
+        }
+    }
+
+    fn advance(&mut self, what: &str) -> Result<&Token, SceneError> {
+        match self.tokens.get(self.next) {
+            Some(_) => {
+                self.next += 1;
+                Ok(&self.tokens[self.next - 1])
+            }
+            None => {
+                let (line, column) = match self.tokens.last() {
+                    Some(last) => (last.line, last.column + last.text.chars().count()),
+                    None => (1, 1),
+                };
+                Err(SceneError {
+                    path: self.path.to_owned(),
+                    line,
+                    column,
+                    message: format!("expected {}, found the end of the file", what),
+                })
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next)
+    }
+
+    fn word(&mut self, what: &str) -> Result<String, SceneError> {
+        let token = self.advance(what)?;
+        if token.kind == TokenKind::Word {
+            Ok(token.text.clone())
+        } else {
+            let message = format!("expected {}, found '{}'", what, token.text);
+            Err(self.error_at(&self.tokens[self.next - 1], message))
+        }
+    }
+
+    fn last(&self) -> &Token {
+        &self.tokens[self.next - 1]
+    }
+
+    fn number(&mut self) -> Result<f64, SceneError> {
+        let text = self.word("a number")?;
+        text.parse::<f64>()
+            .map_err(|_| self.error_at(self.last(), format!("'{}' is not a number", text)))
+    }
+
+    fn count(&mut self) -> Result<usize, SceneError> {
+        let text = self.word("a positive integer")?;
+        match text.parse::<usize>() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => {
+                let message = format!("'{}' is not a positive integer", text);
+                Err(self.error_at(self.last(), message))
+            }
+        }
+    }
+
+    // the depths other than max_depth can be 0: russian roulette from the
+    // first bounce, or a lobe turned off
+    fn depth(&mut self) -> Result<usize, SceneError> {
+        let text = self.word("a non-negative integer")?;
+        text.parse::<usize>().map_err(|_| {
+            let message = format!("'{}' is not a non-negative integer", text);
+            self.error_at(self.last(), message)
+        })
+    }
+
+    fn seed(&mut self) -> Result<u64, SceneError> {
+        let text = self.word("a seed")?;
+        text.parse::<u64>()
+            .map_err(|_| self.error_at(self.last(), format!("'{}' is not a seed", text)))
+    }
+
+    fn vector(&mut self) -> Result<Vector3<f64>, SceneError> {
+        Ok(Vector3::new(self.number()?, self.number()?, self.number()?))
+    }
+
+    fn quoted(&mut self) -> Result<String, SceneError> {
+        let token = self.advance("a quoted string")?;
+        if token.kind == TokenKind::Quoted {
+            Ok(token.text.clone())
+        } else {
+            let message = format!("expected a quoted string, found '{}'", token.text);
+            Err(self.error_at(self.last(), message))
+        }
+    }
+
+    // the opening brace of a block, returns the index of the token for the
+    // errors about missing fields
+    fn open(&mut self) -> Result<usize, SceneError> {
+        let token = self.advance("'{'")?;
+        if token.kind == TokenKind::Open {
+            Ok(self.next - 1)
+        } else {
+            let message = format!("expected '{{', found '{}'", token.text);
+            Err(self.error_at(self.last(), message))
+        }
+    }
+
+    // the next field name of a block, None at the closing brace
+    fn field(&mut self) -> Result<Option<String>, SceneError> {
+        match self.peek() {
+            Some(token) if token.kind == TokenKind::Close => {
+                self.next += 1;
+                Ok(None)
+            }
+            _ => self.word("a field name or '}'").map(Some),
+        }
+    }
+
+    fn unknown_field(&self, block: &str) -> SceneError {
+        let message = format!("unknown field '{}' in {}", self.last().text, block);
+        self.error_at(self.last(), message)
+    }
+
+    fn required<T>(
+        &self,
+        value: Option<T>,
+        field: &str,
+        block: &str,
+        open: usize,
+    ) -> Result<T, SceneError> {
+        // the error points to the keyword that starts the block
+        let start = &self.tokens[open.saturating_sub(1)];
+        value.ok_or_else(|| self.error_at(start, format!("{} needs '{}'", block, field)))
+    }
+
+    fn texture(&mut self) -> Result<Arc<dyn Texture>, SceneError> {
+        let is_name = match self.peek() {
+            Some(token) => token.text.parse::<f64>().is_err(),
+            None => false,
+        };
+        if is_name {
+            let name = self.word("a texture")?;
+            match self.textures.get(&name) {
+                Some(texture) => Ok(texture.clone()),
+                None => Err(self.error_at(self.last(), format!("undefined texture '{}'", name))),
+            }
+        } else {
+            let color = self.vector()?;
+            Ok(Arc::new(SolidTexture::new(color.x, color.y, color.z)))
+        }
+    }
+
+    fn material(&mut self) -> Result<Arc<dyn Material>, SceneError> {
+        let name = self.word("a material name")?;
+        match self.materials.get(&name) {
+            Some(material) => Ok(material.clone()),
+            None => Err(self.error_at(self.last(), format!("undefined material '{}'", name))),
+        }
+    }
+
+    fn prototype(&mut self) -> Result<Arc<dyn Hittable>, SceneError> {
+        let name = self.word("a prototype name")?;
+        match self.prototypes.get(&name) {
+            Some(prototype) => Ok(prototype.clone()),
+            None => Err(self.error_at(self.last(), format!("undefined prototype '{}'", name))),
+        }
+    }
+
+    // an object that is only drawn by the instances
+    fn prototype_definition(&mut self) -> Result<(), SceneError> {
+        let name = self.word("a prototype name")?;
+        let open = self.open()?;
+        let mut object = None;
+        while let Some(field) = self.field()? {
+            object = Some(self.inner_object(&field, object, "a prototype")?);
+        }
+        let object = self.required(object, "an object", "a prototype", open)?;
+        self.prototypes.insert(name, object);
+        Ok(())
+    }
+
+    fn settings(&mut self, settings: &mut RenderSettings) -> Result<(), SceneError> {
+        self.open()?;
+        while let Some(field) = self.field()? {
+            match field.as_str() {
+                "width" => settings.width = self.count()?,
+                "height" => settings.height = self.count()?,
+                "samples" => settings.samples = self.count()?,
+                "max_depth" => settings.depth.max = self.count()?,
+                "min_depth" => settings.depth.min = self.depth()?,
+                "seed" => settings.seed = Some(self.seed()?),
+                "diffuse_depth" => settings.depth.diffuse = self.depth()?,
+                "specular_depth" => settings.depth.specular = self.depth()?,
+                "transmission_depth" => settings.depth.transmission = self.depth()?,
+                "volume_depth" => settings.depth.volume = self.depth()?,
+                "background" => settings.background = self.vector()?,
+                "sampler" => {
+                    let name = self.word("a sampler")?;
+                    settings.sampler = SamplerType::from_name(&name).ok_or_else(|| {
+                        let message = format!("unknown sampler '{}'", name);
+                        self.error_at(self.last(), message)
+                    })?;
+                }
+                "tonemap" => {
+                    let name = self.word("a tone mapping")?;
+                    settings.tone_mapping.operator =
+                        ToneMapOperator::from_name(&name).ok_or_else(|| {
+                            let message = format!("unknown tone mapping '{}'", name);
+                            self.error_at(self.last(), message)
+                        })?;
+                }
+                "exposure" => settings.tone_mapping.exposure = self.number()?,
+                "white" => settings.tone_mapping.white = self.number()?,
+                _ => return Err(self.unknown_field("settings")),
+            }
+        }
+        Ok(())
+    }
+
+    fn camera(&mut self, camera: &mut CameraSettings) -> Result<(), SceneError> {
+        self.open()?;
+        while let Some(field) = self.field()? {
+            match field.as_str() {
+                "look_from" => camera.look_from = self.vector()?,
+                "look_at" => camera.look_at = self.vector()?,
+                "view_up" => camera.view_up = self.vector()?,
+                "fov" => camera.vertical_fov = self.number()?,
+                "aperture" => camera.aperture = self.number()?,
+                "focus_dist" => camera.focus_dist = self.number()?,
+                "time" => {
+                    camera.time0 = self.number()?;
+                    camera.time1 = self.number()?;
+                }
+                _ => return Err(self.unknown_field("camera")),
+            }
+        }
+        Ok(())
+    }
+
+    fn background(&mut self) -> Result<Arc<dyn Background>, SceneError> {
+        let kind = self.word("a background kind")?;
+        let kind_token = self.next - 1;
+        let open = self.open()?;
+        let background: Arc<dyn Background> = match kind.as_str() {
+            "constant" => {
+                let mut color = None;
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "color" => color = Some(self.vector()?),
+                        _ => return Err(self.unknown_field("a constant background")),
+                    }
+                }
+                Arc::new(self.required(color, "color", "a constant background", open)?)
+            }
+            "gradient" => {
+                let mut gradient = Gradient::sky();
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "bottom" => gradient.bottom = self.vector()?,
+                        "top" => gradient.top = self.vector()?,
+                        _ => return Err(self.unknown_field("a gradient background")),
+                    }
+                }
+                Arc::new(gradient)
+            }
+            "environment" => {
+                let (mut file, mut intensity, mut rotation) = (None, 1.0, 0.0);
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "file" => file = Some((self.quoted()?, self.next - 1)),
+                        "intensity" => intensity = self.number()?,
+                        "rotation" => rotation = self.number()?,
+                        _ => return Err(self.unknown_field("an environment background")),
+                    }
+                }
+                let (file, token) =
+                    self.required(file, "file", "an environment background", open)?;
+                let mut map = EnvironmentMap::open(self.directory.join(&file)).map_err(|e| {
+                    self.error_at(
+                        &self.tokens[token],
+                        format!("cannot open '{}': {}", file, e),
+                    )
+                })?;
+                map.intensity = intensity;
+                map.rotation = rotation;
+                Arc::new(map)
+            }
+            _ => {
+                let message = format!("unknown background kind '{}'", kind);
+                return Err(self.error_at(&self.tokens[kind_token], message));
+            }
+        };
+        Ok(background)
+    }
+
+    fn texture_definition(&mut self) -> Result<(), SceneError> {
+        let name = self.word("a texture name")?;
+        let kind = self.word("a texture kind")?;
+        let kind_token = self.next - 1;
+        let open = self.open()?;
+        let texture: Arc<dyn Texture> = match kind.as_str() {
+            "solid" => {
+                let mut color = None;
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "color" => color = Some(self.vector()?),
+                        _ => return Err(self.unknown_field("a solid texture")),
+                    }
+                }
+                let color = self.required(color, "color", "a solid texture", open)?;
+                Arc::new(SolidTexture::new(color.x, color.y, color.z))
+            }
+            "checker" => {
+                let (mut odd, mut even) = (None, None);
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "odd" => odd = Some(self.texture()?),
+                        "even" => even = Some(self.texture()?),
+                        _ => return Err(self.unknown_field("a checker texture")),
+                    }
+                }
+                Arc::new(CheckerTexture::new(
+                    self.required(odd, "odd", "a checker texture", open)?,
+                    self.required(even, "even", "a checker texture", open)?,
+                ))
+            }
+            "noise" => {
+                let mut scale = 1.0;
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "scale" => scale = self.number()?,
+                        _ => return Err(self.unknown_field("a noise texture")),
+                    }
+                }
+                Arc::new(NoiseTexture::new(scale))
+            }
+            "image" => {
+                let mut file = None;
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "file" => file = Some((self.quoted()?, self.next - 1)),
+                        _ => return Err(self.unknown_field("an image texture")),
+                    }
+                }
+                let (file, token) = self.required(file, "file", "an image texture", open)?;
+                let texture = ImageTexture::open(self.directory.join(&file)).map_err(|e| {
+                    self.error_at(
+                        &self.tokens[token],
+                        format!("cannot open '{}': {}", file, e),
+                    )
+                })?;
+                Arc::new(texture)
+            }
+            _ => {
+                let message = format!("unknown texture kind '{}'", kind);
+                return Err(self.error_at(&self.tokens[kind_token], message));
+            }
+        };
+        self.textures.insert(name, texture);
+        Ok(())
+    }
+
+    fn material_definition(&mut self) -> Result<(), SceneError> {
+        let name = self.word("a material name")?;
+        let kind = self.word("a material kind")?;
+        // the field holding the texture of the material, if it has one
+        let texture_field = match kind.as_str() {
+            "lambertian" | "metal" | "isotropic" => Some("albedo"),
+            "diffuse_light" => Some("emit"),
+            "dielectric" => None,
+            _ => {
+                let message = format!("unknown material kind '{}'", kind);
+                return Err(self.error_at(self.last(), message));
+            }
+        };
+        let open = self.open()?;
+        let block = format!("a {} material", kind);
+        let (mut texture, mut fuzz, mut ior) = (None, 0.0, None);
+        while let Some(field) = self.field()? {
+            match (kind.as_str(), field.as_str()) {
+                (_, field) if Some(field) == texture_field => texture = Some(self.texture()?),
+                ("metal", "fuzz") => fuzz = self.number()?,
+                ("dielectric", "ior") => ior = Some(self.number()?),
+                _ => return Err(self.unknown_field(&block)),
+            }
+        }
+        let material: Arc<dyn Material> = match texture_field {
+            Some(field) => {
+                let texture = self.required(texture, field, &block, open)?;
+                match kind.as_str() {
+                    "lambertian" => Arc::new(Lambertian::new(texture)),
+                    "metal" => Arc::new(Metal::new(texture, fuzz)),
+                    "isotropic" => Arc::new(Isotropic::new(texture)),
+                    _ => Arc::new(DiffuseLight::new(texture)),
+                }
+            }
+            None => Arc::new(Dielectric::new(self.required(ior, "ior", &block, open)?)),
+        };
+        self.materials.insert(name, material);
+        Ok(())
+    }
+
+    // an object keyword has just been read
+    fn object(&mut self, kind: &str) -> Result<Arc<dyn Hittable>, SceneError> {
+        let open = self.open()?;
+        let object: Arc<dyn Hittable> = match kind {
+            "sphere" => {
+                let (mut center, mut radius, mut material) = (None, None, None);
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "center" => center = Some(self.vector()?),
+                        "radius" => radius = Some(self.number()?),
+                        "material" => material = Some(self.material()?),
+                        _ => return Err(self.unknown_field("a sphere")),
+                    }
+                }
+                Arc::new(Sphere::new(
+                    self.required(center, "center", "a sphere", open)?,
+                    self.required(radius, "radius", "a sphere", open)?,
+                    self.required(material, "material", "a sphere", open)?,
+                ))
+            }
+            "moving_sphere" => {
+                let (mut center0, mut center1, mut time0, mut time1) = (None, None, 0.0, 1.0);
+                let (mut radius, mut material) = (None, None);
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "center0" => center0 = Some(self.vector()?),
+                        "center1" => center1 = Some(self.vector()?),
+                        "time0" => time0 = self.number()?,
+                        "time1" => time1 = self.number()?,
+                        "radius" => radius = Some(self.number()?),
+                        "material" => material = Some(self.material()?),
+                        _ => return Err(self.unknown_field("a moving sphere")),
+                    }
+                }
+                let block = "a moving sphere";
+                Arc::new(MovingSphere::new(
+                    self.required(center0, "center0", block, open)?,
+                    self.required(center1, "center1", block, open)?,
+                    time0,
+                    time1,
+                    self.required(radius, "radius", block, open)?,
+                    self.required(material, "material", block, open)?,
+                ))
+            }
+            "rect" => {
+                // min and max are the coordinates on the two axes of the plane,
+                // in the order of its name, k is the position on the third axis
+                let (mut plane, mut min, mut max, mut k, mut material) =
+                    (None, None, None, None, None);
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "plane" => {
+                            plane = Some(match self.word("a plane")?.as_str() {
+                                "xy" => Plane::XY,
+                                "yz" => Plane::YZ,
+                                "zx" => Plane::ZX,
+                                other => {
+                                    let message =
+                                        format!("unknown plane '{}', use xy, yz or zx", other);
+                                    return Err(self.error_at(self.last(), message));
+                                }
+                            })
+                        }
+                        "min" => min = Some((self.number()?, self.number()?)),
+                        "max" => max = Some((self.number()?, self.number()?)),
+                        "k" => k = Some(self.number()?),
+                        "material" => material = Some(self.material()?),
+                        _ => return Err(self.unknown_field("a rect")),
+                    }
+                }
+                let (x0, y0) = self.required(min, "min", "a rect", open)?;
+                let (x1, y1) = self.required(max, "max", "a rect", open)?;
+                Arc::new(Rect::new(
+                    self.required(plane, "plane", "a rect", open)?,
+                    x0,
+                    y0,
+                    x1,
+                    y1,
+                    self.required(k, "k", "a rect", open)?,
+                    self.required(material, "material", "a rect", open)?,
+                ))
+            }
+            // a parallelogram, u and v are its two edges from the corner
+            "quad" => {
+                let (mut corner, mut u, mut v, mut material) = (None, None, None, None);
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "corner" => corner = Some(self.vector()?),
+                        "u" => u = Some(self.vector()?),
+                        "v" => v = Some(self.vector()?),
+                        "material" => material = Some(self.material()?),
+                        _ => return Err(self.unknown_field("a quad")),
+                    }
+                }
+                let u = self.required(u, "u", "a quad", open)?;
+                let v = self.required(v, "v", "a quad", open)?;
+                if u.cross(&v).norm() == 0.0 {
+                    let message = "the edges of a quad cannot be parallel".to_owned();
+                    return Err(self.error_at(&self.tokens[open - 1], message));
+                }
+                Arc::new(Quad::new(
+                    self.required(corner, "corner", "a quad", open)?,
+                    u,
+                    v,
+                    self.required(material, "material", "a quad", open)?,
+                ))
+            }
+            "disk" => {
+                let (mut center, mut normal, mut radius, mut material) = (None, None, None, None);
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "center" => center = Some(self.vector()?),
+                        "normal" => normal = Some(self.normal()?),
+                        "radius" => radius = Some(self.number()?),
+                        "material" => material = Some(self.material()?),
+                        _ => return Err(self.unknown_field("a disk")),
+                    }
+                }
+                Arc::new(Disk::new(
+                    self.required(center, "center", "a disk", open)?,
+                    self.required(normal, "normal", "a disk", open)?,
+                    self.required(radius, "radius", "a disk", open)?,
+                    self.required(material, "material", "a disk", open)?,
+                ))
+            }
+            "plane" => {
+                let (mut point, mut normal, mut material) = (None, None, None);
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "point" => point = Some(self.vector()?),
+                        "normal" => normal = Some(self.normal()?),
+                        "material" => material = Some(self.material()?),
+                        _ => return Err(self.unknown_field("a plane")),
+                    }
+                }
+                Arc::new(InfinitePlane::new(
+                    self.required(point, "point", "a plane", open)?,
+                    self.required(normal, "normal", "a plane", open)?,
+                    self.required(material, "material", "a plane", open)?,
+                ))
+            }
+            // on the y axis, starting from center
+            "cylinder" | "cone" => {
+                let block = if kind == "cone" {
+                    "a cone"
+                } else {
+                    "a cylinder"
+                };
+                let (mut center, mut radius, mut height, mut material) = (None, None, None, None);
+                let (mut sweep, mut capped) = (360.0, true);
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "center" => center = Some(self.vector()?),
+                        "radius" => radius = Some(self.number()?),
+                        "height" => height = Some(self.number()?),
+                        "sweep" => sweep = self.number()?,
+                        "uncapped" => capped = false,
+                        "material" => material = Some(self.material()?),
+                        _ => return Err(self.unknown_field(block)),
+                    }
+                }
+                let center = self.required(center, "center", block, open)?;
+                let radius = self.required(radius, "radius", block, open)?;
+                let height = self.required(height, "height", block, open)?;
+                let material = self.required(material, "material", block, open)?;
+                if kind == "cone" {
+                    let cone = Cone::new(center, radius, height, material).with_sweep(sweep);
+                    if capped {
+                        Arc::new(cone)
+                    } else {
+                        Arc::new(cone.uncapped())
+                    }
+                } else {
+                    let cylinder =
+                        Cylinder::new(center, radius, height, material).with_sweep(sweep);
+                    if capped {
+                        Arc::new(cylinder)
+                    } else {
+                        Arc::new(cylinder.uncapped())
+                    }
+                }
+            }
+            "capsule" => {
+                let (mut start, mut end, mut radius, mut material) = (None, None, None, None);
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "start" => start = Some(self.vector()?),
+                        "end" => end = Some(self.vector()?),
+                        "radius" => radius = Some(self.number()?),
+                        "material" => material = Some(self.material()?),
+                        _ => return Err(self.unknown_field("a capsule")),
+                    }
+                }
+                Arc::new(Capsule::new(
+                    self.required(start, "start", "a capsule", open)?,
+                    self.required(end, "end", "a capsule", open)?,
+                    self.required(radius, "radius", "a capsule", open)?,
+                    self.required(material, "material", "a capsule", open)?,
+                ))
+            }
+            "torus" => {
+                let (mut center, mut major, mut minor, mut material) = (None, None, None, None);
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "center" => center = Some(self.vector()?),
+                        "major" => major = Some(self.number()?),
+                        "minor" => minor = Some(self.number()?),
+                        "material" => material = Some(self.material()?),
+                        _ => return Err(self.unknown_field("a torus")),
+                    }
+                }
+                Arc::new(Torus::new(
+                    self.required(center, "center", "a torus", open)?,
+                    self.required(major, "major", "a torus", open)?,
+                    self.required(minor, "minor", "a torus", open)?,
+                    self.required(material, "material", "a torus", open)?,
+                ))
+            }
+            "cube" => {
+                let (mut min, mut max, mut material) = (None, None, None);
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "min" => min = Some(self.vector()?),
+                        "max" => max = Some(self.vector()?),
+                        "material" => material = Some(self.material()?),
+                        _ => return Err(self.unknown_field("a cube")),
+                    }
+                }
+                Arc::new(Cube::new(
+                    self.required(min, "min", "a cube", open)?,
+                    self.required(max, "max", "a cube", open)?,
+                    self.required(material, "material", "a cube", open)?,
+                ))
+            }
+            "medium" => {
+                let (mut density, mut albedo, mut boundary) = (None, None, None);
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "density" => density = Some(self.number()?),
+                        "albedo" => albedo = Some(self.texture()?),
+                        _ => boundary = Some(self.inner_object(&field, boundary, "a medium")?),
+                    }
+                }
+                Arc::new(ConstantMedium::new(
+                    self.required(boundary, "a boundary object", "a medium", open)?,
+                    self.required(density, "density", "a medium", open)?,
+                    self.required(albedo, "albedo", "a medium", open)?,
+                ))
+            }
+            "rotate" => {
+                let (mut axis, mut angle, mut object) = (None, None, None);
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "axis" => {
+                            axis = Some(match self.word("an axis")?.as_str() {
+                                "x" => Axis::X,
+                                "y" => Axis::Y,
+                                "z" => Axis::Z,
+                                other => {
+                                    let message = format!("unknown axis '{}'", other);
+                                    return Err(self.error_at(self.last(), message));
+                                }
+                            })
+                        }
+                        "angle" => angle = Some(self.number()?),
+                        _ => object = Some(self.inner_object(&field, object, "rotate")?),
+                    }
+                }
+                Arc::new(Rotate::new(
+                    self.required(axis, "axis", "rotate", open)?,
+                    self.required(object, "an object", "rotate", open)?,
+                    self.required(angle, "angle", "rotate", open)?,
+                ))
+            }
+            "translate" => {
+                let (mut offset, mut object) = (None, None);
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "offset" => offset = Some(self.vector()?),
+                        _ => object = Some(self.inner_object(&field, object, "translate")?),
+                    }
+                }
+                Arc::new(Traslate::new(
+                    self.required(object, "an object", "translate", open)?,
+                    self.required(offset, "offset", "translate", open)?,
+                ))
+            }
+            // scale, rotate (an axis and an angle) and translate are applied
+            // in the order they are written
+            "transform" => {
+                let (mut matrix, mut object) = (Matrix4::identity(), None);
+                while let Some(field) = self.field()? {
+                    match self.transform_step(&field)? {
+                        Some(step) => matrix = step * matrix,
+                        None => object = Some(self.inner_object(&field, object, "transform")?),
+                    }
+                }
+                Arc::new(Transform::new(
+                    self.required(object, "an object", "transform", open)?,
+                    matrix,
+                ))
+            }
+            // a copy of a prototype, moved like a transform
+            "instance" => {
+                let (mut matrix, mut prototype, mut material) = (Matrix4::identity(), None, None);
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "prototype" => prototype = Some(self.prototype()?),
+                        "material" => {
+                            let paint = self.material()?;
+                            if paint.is_emissive() {
+                                let message = "the material of an instance cannot be emissive, \
+                                               the lights go in the prototype"
+                                    .to_owned();
+                                return Err(self.error_at(self.last(), message));
+                            }
+                            material = Some(paint);
+                        }
+                        _ => match self.transform_step(&field)? {
+                            Some(step) => matrix = step * matrix,
+                            None => return Err(self.unknown_field("an instance")),
+                        },
+                    }
+                }
+                let prototype = self.required(prototype, "prototype", "an instance", open)?;
+                Arc::new(match material {
+                    Some(material) => Instance::with_material(prototype, matrix, material),
+                    None => Instance::new(prototype, matrix),
+                })
+            }
+            // every key has a time, a scale, a rotation (an axis and an
+            // angle) and a translation, the missing ones do not transform
+            "animate" => {
+                let (mut keyframes, mut object) = (Vec::new(), None);
+                while let Some(field) = self.field()? {
+                    match field.as_str() {
+                        "key" => keyframes.push(self.keyframe()?),
+                        _ => object = Some(self.inner_object(&field, object, "animate")?),
+                    }
+                }
+                if keyframes.is_empty() {
+                    let message = "animate needs at least a key".to_owned();
+                    return Err(self.error_at(&self.tokens[open - 1], message));
+                }
+                Arc::new(AnimatedTransform::new(
+                    self.required(object, "an object", "animate", open)?,
+                    keyframes,
+                ))
+            }
+            "flip" => {
+                let mut object = None;
+                while let Some(field) = self.field()? {
+                    object = Some(self.inner_object(&field, object, "flip")?);
+                }
+                Arc::new(FlipNormals::new(self.required(
+                    object,
+                    "an object",
+                    "flip",
+                    open,
+                )?))
+            }
+            "bvh" => {
+                let mut objects = Vec::new();
+                while let Some(field) = self.field()? {
+                    objects.push(self.inner_object(&field, None, "bvh")?);
+                }
+                if objects.is_empty() {
+                    let message = "bvh needs at least an object".to_owned();
+                    return Err(self.error_at(&self.tokens[open - 1], message));
+                }
+                Arc::new(BVHNode::from_list(objects, 0.0, 1.0))
+            }
+            _ => unreachable!(),
+        };
+        Ok(object)
+    }
+
+    fn scale(&mut self) -> Result<Vector3<f64>, SceneError> {
+        let scale = self.vector()?;
+        if scale.iter().any(|&s| s == 0.0) {
+            let message = "a scale cannot be 0".to_owned();
+            return Err(self.error_at(self.last(), message));
+        }
+        Ok(scale)
+    }
+
+    fn axis(&mut self) -> Result<Vector3<f64>, SceneError> {
+        let axis = self.vector()?;
+        if axis.norm() == 0.0 {
+            let message = "the axis of a rotation cannot be 0".to_owned();
+            return Err(self.error_at(self.last(), message));
+        }
+        Ok(axis)
+    }
+
+    fn normal(&mut self) -> Result<Vector3<f64>, SceneError> {
+        let normal = self.vector()?;
+        if normal.norm() == 0.0 {
+            let message = "a normal cannot be 0".to_owned();
+            return Err(self.error_at(self.last(), message));
+        }
+        Ok(normal)
+    }
+
+    // the matrix of a scale, a rotation (an axis and an angle) or a
+    // translation field, None for the other fields
+    fn transform_step(&mut self, field: &str) -> Result<Option<Matrix4<f64>>, SceneError> {
+        Ok(Some(match field {
+            "scale" => transform::scaling(&self.scale()?),
+            "rotate" => {
+                let axis = self.axis()?;
+                transform::rotation(&axis, self.number()?)
+            }
+            "translate" => transform::translation(&self.vector()?),
+            _ => return Ok(None),
+        }))
+    }
+
+    fn keyframe(&mut self) -> Result<Keyframe, SceneError> {
+        let open = self.open()?;
+        let mut time = None;
+        let mut keyframe = Keyframe::new(0.0);
+        while let Some(field) = self.field()? {
+            match field.as_str() {
+                "time" => time = Some(self.number()?),
+                "translate" => keyframe.translation = self.vector()?,
+                "rotate" => {
+                    let axis = Unit::new_normalize(self.axis()?);
+                    let angle = self.number()?.to_radians();
+                    keyframe.rotation = UnitQuaternion::from_axis_angle(&axis, angle);
+                }
+                "scale" => keyframe.scale = self.scale()?,
+                _ => return Err(self.unknown_field("a key")),
+            }
+        }
+        keyframe.time = self.required(time, "time", "a key", open)?;
+        Ok(keyframe)
+    }
+
+    // an object nested in a wrapper, the wrappers other than bvh hold only one
+    fn inner_object(
+        &mut self,
+        kind: &str,
+        previous: Option<Arc<dyn Hittable>>,
+        block: &str,
+    ) -> Result<Arc<dyn Hittable>, SceneError> {
+        if !OBJECTS.contains(&kind) {
+            return Err(self.unknown_field(block));
+        }
+        if previous.is_some() {
+            let message = format!("{} can hold only one object", block);
+            return Err(self.error_at(self.last(), message));
+        }
+        self.object(kind)
+    }
+
+    fn scene(&mut self) -> Result<Scene, SceneError> {
+        let mut settings = RenderSettings::default();
+        let mut camera = CameraSettings::default();
+        let mut world = HittableList::default();
+        let mut background = None;
+        while self.peek().is_some() {
+            let keyword = self.word("a block keyword")?;
+            match keyword.as_str() {
+                "settings" => self.settings(&mut settings)?,
+                "camera" => self.camera(&mut camera)?,
+                "background" => background = Some(self.background()?),
+                "texture" => self.texture_definition()?,
+                "material" => self.material_definition()?,
+                "prototype" => self.prototype_definition()?,
+                kind if OBJECTS.contains(&kind) => world.push(self.object(kind)?),
+                _ => {
+                    let message = format!("unknown block '{}'", keyword);
+                    return Err(self.error_at(self.last(), message));
+                }
+            }
+        }
+        Ok(Scene {
+            world: Box::new(world),
+            camera,
+            settings,
+            background,
+        })
+    }
+}
+
+fn parse_scene(source: &str, path: &Path, directory: PathBuf) -> Result<Scene, SceneError> {
+    let tokens = tokenize(source).map_err(|(line, column, message)| SceneError {
+        path: path.to_owned(),
+        line,
+        column,
+        message,
+    })?;
+    Parser {
+        path,
+        directory,
+        tokens,
+        next: 0,
+        textures: HashMap::new(),
+        materials: HashMap::new(),
+        prototypes: HashMap::new(),
+    }
+    .scene()
+}
+
+// image files are searched relative to the current directory
+pub fn parse(source: &str) -> Result<Scene, SceneError> {
+    parse_scene(source, Path::new("<scene>"), PathBuf::new())
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Scene, SceneError> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(|e| SceneError {
+        path: path.to_owned(),
+        line: 0,
+        column: 0,
+        message: e.to_string(),
+    })?;
+    let directory = path.parent().map(Path::to_owned).unwrap_or_default();
+    parse_scene(&source, path, directory)
+}
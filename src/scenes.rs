@@ -0,0 +1,588 @@
+use nalgebra::Vector3;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
+
+use crate::bvh::BVHNode;
+use crate::camera::CameraSettings;
+use crate::cube::Cube;
+use crate::hittable::{FlipNormals, Hittable, HittableList};
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Metal};
+use crate::medium::ConstantMedium;
+use crate::rect::{Plane, Rect};
+use crate::renderer::RenderSettings;
+use crate::rotate::{Axis, Rotate};
+use crate::scene::Scene;
+use crate::sphere::{MovingSphere, Sphere};
+use crate::texture::{CheckerTexture, ImageTexture, NoiseTexture, SolidTexture};
+use crate::traslate::Traslate;
+
+/*
+* The scenes of the books, each one with the camera and the render settings
+* it was made for. none of them has a background, the rays that leave the
+* scene get the black of the settings
+*/
+
+// relative to the root of the repository
+const EARTH_TEXTURE: &str = "texture/earthmap.jpg";
+
+pub const NAMES: [&str; 8] = [
+    "random_scene",
+    "two_spheres",
+    "two_perlin_spheres",
+    "earth",
+    "simple_light",
+    "cornell_box",
+    "cornell_smoke",
+    "final_scene",
+];
+
+pub fn by_name(name: &str) -> Option<Scene> {
+    match name {
+        "random_scene" => Some(random_scene()),
+        "two_spheres" => Some(two_spheres()),
+        "two_perlin_spheres" => Some(two_perlin_spheres()),
+        "earth" => Some(earth()),
+        "simple_light" => Some(simple_light()),
+        "cornell_box" => Some(cornell_box()),
+        "cornell_smoke" => Some(cornell_smoke()),
+        "final_scene" => Some(final_scene()),
+        _ => None,
+    }
+}
+
+// all the scenes of the books use the same lens and shutter
+fn camera(look_from: Vector3<f64>, look_at: Vector3<f64>, vertical_fov: f64) -> CameraSettings {
+    CameraSettings {
+        look_from,
+        look_at,
+        view_up: Vector3::new(0.0, 1.0, 0.0),
+        vertical_fov,
+        aperture: 0.1,
+        focus_dist: 10.0,
+        time0: 0.0,
+        time1: 1.0,
+    }
+}
+
+fn settings(width: usize, height: usize, samples: usize) -> RenderSettings {
+    RenderSettings {
+        width,
+        height,
+        samples,
+        ..Default::default()
+    }
+}
+
+pub fn random_scene() -> Scene {
+    Scene {
+        world: random_scene_world(),
+        camera: camera(Vector3::new(13.0, 2.0, 3.0), Vector3::zeros(), 20.0),
+        settings: settings(1280, 720, 100),
+        background: None,
+    }
+}
+
+pub fn two_spheres() -> Scene {
+    Scene {
+        world: two_spheres_world(),
+        camera: camera(Vector3::new(13.0, 2.0, 3.0), Vector3::zeros(), 20.0),
+        settings: settings(1280, 720, 10),
+        background: None,
+    }
+}
+
+pub fn two_perlin_spheres() -> Scene {
+    Scene {
+        world: two_perlin_spheres_world(),
+        camera: camera(Vector3::new(13.0, 2.0, 3.0), Vector3::zeros(), 20.0),
+        settings: settings(1280, 720, 10),
+        background: None,
+    }
+}
+
+// needs the texture of the earth, so it must be built from the root of the repository
+pub fn earth() -> Scene {
+    Scene {
+        world: earth_world(),
+        camera: camera(Vector3::new(13.0, 2.0, 3.0), Vector3::zeros(), 20.0),
+        settings: settings(1280, 720, 10),
+        background: None,
+    }
+}
+
+pub fn simple_light() -> Scene {
+    Scene {
+        world: simple_light_world(),
+        camera: camera(Vector3::new(13.0, 3.0, 3.0), Vector3::zeros(), 50.0),
+        settings: settings(1280, 720, 100),
+        background: None,
+    }
+}
+
+pub fn cornell_box() -> Scene {
+    Scene {
+        world: cornell_box_world(),
+        camera: cornell_camera(),
+        settings: settings(800, 800, 100),
+        background: None,
+    }
+}
+
+pub fn cornell_smoke() -> Scene {
+    Scene {
+        world: cornell_smoke_world(),
+        camera: cornell_camera(),
+        settings: settings(800, 800, 100),
+        background: None,
+    }
+}
+
+// needs the texture of the earth, like earth()
+pub fn final_scene() -> Scene {
+    Scene {
+        world: final_scene_world(),
+        camera: camera(
+            Vector3::new(478.0, 278.0, -600.0),
+            Vector3::new(278.0, 278.0, 0.0),
+            40.0,
+        ),
+        settings: settings(800, 800, 100),
+        background: None,
+    }
+}
+
+fn cornell_camera() -> CameraSettings {
+    camera(
+        Vector3::new(278.0, 278.0, -800.0),
+        Vector3::new(278.0, 278.0, 0.0),
+        40.0,
+    )
+}
+
+// the spheres are placed with a fixed seed, the scene is the same every time
+fn random_scene_world() -> Box<dyn Hittable> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let origin = Vector3::new(4.0, 0.2, 0.0);
+    let mut world: Vec<Arc<dyn Hittable>> = Vec::new();
+    let checker = CheckerTexture::new(
+        SolidTexture::new(0.2, 0.3, 0.1),
+        SolidTexture::new(0.9, 0.9, 0.9),
+    );
+    world.push(Arc::new(Sphere::new(
+        Vector3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(checker),
+    )));
+
+    for a in -10..10 {
+        for b in -10..10 {
+            let choose_material = rng.gen::<f64>();
+            let center = Vector3::new(
+                a as f64 + 0.9 * rng.gen::<f64>(),
+                0.2,
+                b as f64 + 0.9 * rng.gen::<f64>(),
+            );
+            if (center - origin).magnitude() > 0.9 {
+                if choose_material < 0.8 {
+                    // diffuse
+                    world.push(Arc::new(MovingSphere::new(
+                        center,
+                        center + Vector3::new(0.0, 0.5 * rng.gen::<f64>(), 0.0),
+                        0.0,
+                        1.0,
+                        0.2,
+                        Lambertian::new(SolidTexture::new(
+                            rng.gen::<f64>() * rng.gen::<f64>(),
+                            rng.gen::<f64>() * rng.gen::<f64>(),
+                            rng.gen::<f64>() * rng.gen::<f64>(),
+                        )),
+                    )));
+                } else if choose_material < 0.95 {
+                    // metal
+                    world.push(Arc::new(Sphere::new(
+                        center,
+                        0.2,
+                        Metal::new(
+                            SolidTexture::new(
+                                0.5 * (1.0 + rng.gen::<f64>()),
+                                0.5 * (1.0 + rng.gen::<f64>()),
+                                0.5 * (1.0 + rng.gen::<f64>()),
+                            ),
+                            0.5 * rng.gen::<f64>(),
+                        ),
+                    )));
+                } else {
+                    // glass
+                    world.push(Arc::new(Sphere::new(center, 0.2, Dielectric::new(1.5))));
+                }
+            }
+        }
+    }
+    world.push(Arc::new(Sphere::new(
+        Vector3::new(0.0, 1.0, 0.0),
+        1.0,
+        Dielectric::new(1.5),
+    )));
+    world.push(Arc::new(Sphere::new(
+        Vector3::new(-4.0, 1.0, 0.0),
+        1.0,
+        Lambertian::new(SolidTexture::new(0.4, 0.2, 0.1)),
+    )));
+    world.push(Arc::new(Sphere::new(
+        Vector3::new(4.0, 1.0, 0.0),
+        1.0,
+        Metal::new(SolidTexture::new(0.7, 0.6, 0.5), 0.0),
+    )));
+    Box::new(BVHNode::new(&mut world, 0.0, 1.0))
+}
+
+fn two_spheres_world() -> Box<dyn Hittable> {
+    let checker = CheckerTexture::new(
+        SolidTexture::new(0.2, 0.3, 0.1),
+        SolidTexture::new(0.9, 0.9, 0.9),
+    );
+    let mut world = HittableList::default();
+    world.push(Sphere::new(
+        Vector3::new(0.0, -10.0, 0.0),
+        10.0,
+        Lambertian::new(checker.clone()),
+    ));
+    world.push(Sphere::new(
+        Vector3::new(0.0, 10.0, 0.0),
+        10.0,
+        Lambertian::new(checker),
+    ));
+    Box::new(world)
+}
+
+fn two_perlin_spheres_world() -> Box<dyn Hittable> {
+    let noise = NoiseTexture::new(4.0);
+    let mut world = HittableList::default();
+    world.push(Sphere::new(
+        Vector3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(noise.clone()),
+    ));
+    world.push(Sphere::new(
+        Vector3::new(0.0, 2.0, 0.0),
+        2.0,
+        Lambertian::new(noise),
+    ));
+    Box::new(world)
+}
+
+fn earth_world() -> Box<dyn Hittable> {
+    let texture = ImageTexture::open(EARTH_TEXTURE).expect("image not found");
+    let earth = Sphere::new(Vector3::new(0.0, 0.0, 0.0), 2.0, Lambertian::new(texture));
+    Box::new(earth)
+}
+
+fn simple_light_world() -> Box<dyn Hittable> {
+    let noise = NoiseTexture::new(4.0);
+    let mut world = HittableList::default();
+
+    world.push(Sphere::new(
+        Vector3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(noise.clone()),
+    ));
+    world.push(Sphere::new(
+        Vector3::new(0.0, 2.0, 0.0),
+        2.0,
+        Lambertian::new(noise),
+    ));
+    world.push(Sphere::new(
+        Vector3::new(0.0, 7.0, 0.0),
+        2.0,
+        DiffuseLight::new(SolidTexture::new(4.0, 4.0, 4.0)),
+    ));
+    world.push(Rect::new(
+        Plane::XY,
+        3.0,
+        1.0,
+        5.0,
+        3.0,
+        -2.0,
+        DiffuseLight::new(SolidTexture::new(4.0, 4.0, 4.0)),
+    ));
+    Box::new(world)
+}
+
+fn cornell_box_world() -> Box<dyn Hittable> {
+    let red = Lambertian::new(SolidTexture::new(0.65, 0.05, 0.05));
+    let white = Lambertian::new(SolidTexture::new(0.73, 0.73, 0.73));
+    let green = Lambertian::new(SolidTexture::new(0.12, 0.45, 0.15));
+
+    let light = DiffuseLight::new(SolidTexture::new(15.0, 15.0, 15.0));
+    let mut world = HittableList::default();
+    world.push(FlipNormals::new(Rect::new(
+        Plane::YZ,
+        0.0,
+        0.0,
+        555.0,
+        555.0,
+        555.0,
+        green,
+    )));
+    world.push(Rect::new(Plane::YZ, 0.0, 0.0, 555.0, 555.0, 0.0, red));
+    world.push(Rect::new(
+        Plane::ZX,
+        227.0,
+        213.0,
+        332.0,
+        343.0,
+        554.0,
+        light,
+    ));
+    world.push(FlipNormals::new(Rect::new(
+        Plane::ZX,
+        0.0,
+        0.0,
+        555.0,
+        555.0,
+        555.0,
+        white.clone(),
+    )));
+    world.push(Rect::new(
+        Plane::ZX,
+        0.0,
+        0.0,
+        555.0,
+        555.0,
+        0.0,
+        white.clone(),
+    ));
+    world.push(FlipNormals::new(Rect::new(
+        Plane::XY,
+        0.0,
+        0.0,
+        555.0,
+        555.0,
+        555.0,
+        white.clone(),
+    )));
+
+    world.push(Traslate::new(
+        Rotate::new(
+            Axis::Y,
+            Cube::new(
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(165.0, 165.0, 165.0),
+                white.clone(),
+            ),
+            -18.0,
+        ),
+        Vector3::new(130.0, 0.0, 65.0),
+    ));
+
+    world.push(Traslate::new(
+        Rotate::new(
+            Axis::Y,
+            Cube::new(
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(165.0, 330.0, 165.0),
+                white,
+            ),
+            15.0,
+        ),
+        Vector3::new(265.0, 0.0, 295.0),
+    ));
+
+    Box::new(world)
+}
+
+fn cornell_smoke_world() -> Box<dyn Hittable> {
+    let red = Lambertian::new(SolidTexture::new(0.65, 0.05, 0.05));
+    let white = Lambertian::new(SolidTexture::new(0.73, 0.73, 0.73));
+    let green = Lambertian::new(SolidTexture::new(0.12, 0.45, 0.15));
+    let light = DiffuseLight::new(SolidTexture::new(7.0, 7.0, 7.0));
+    let mut world = HittableList::default();
+
+    world.push(FlipNormals::new(Rect::new(
+        Plane::YZ,
+        0.0,
+        0.0,
+        555.0,
+        555.0,
+        555.0,
+        green,
+    )));
+    world.push(Rect::new(Plane::YZ, 0.0, 0.0, 555.0, 555.0, 0.0, red));
+    world.push(Rect::new(
+        Plane::ZX,
+        127.0,
+        113.0,
+        432.0,
+        443.0,
+        554.0,
+        light,
+    ));
+    world.push(FlipNormals::new(Rect::new(
+        Plane::ZX,
+        0.0,
+        0.0,
+        555.0,
+        555.0,
+        0.0,
+        white.clone(),
+    )));
+    world.push(Rect::new(
+        Plane::ZX,
+        0.0,
+        0.0,
+        555.0,
+        555.0,
+        555.0,
+        white.clone(),
+    ));
+    world.push(FlipNormals::new(Rect::new(
+        Plane::XY,
+        0.0,
+        0.0,
+        555.0,
+        555.0,
+        0.0,
+        white.clone(),
+    )));
+
+    let box1 = Traslate::new(
+        Rotate::new(
+            Axis::Y,
+            Cube::new(
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(165.0, 165.0, 165.0),
+                white.clone(),
+            ),
+            -18.0,
+        ),
+        Vector3::new(130.0, 0.0, 65.0),
+    );
+
+    let box2 = Traslate::new(
+        Rotate::new(
+            Axis::Y,
+            Cube::new(
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(165.0, 330.0, 165.0),
+                white,
+            ),
+            15.0,
+        ),
+        Vector3::new(265.0, 0.0, 295.0),
+    );
+
+    world.push(ConstantMedium::new(
+        box1,
+        0.01,
+        SolidTexture::new(1.0, 1.0, 1.0),
+    ));
+    world.push(ConstantMedium::new(
+        box2,
+        0.01,
+        SolidTexture::new(0.0, 0.0, 0.0),
+    ));
+
+    Box::new(world)
+}
+
+fn final_scene_world() -> Box<dyn Hittable> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let white = Lambertian::new(SolidTexture::new(0.73, 0.73, 0.73));
+    let ground = Lambertian::new(SolidTexture::new(0.48, 0.83, 0.53));
+    let mut world = HittableList::default();
+    let mut box_list1: Vec<Arc<dyn Hittable>> = Vec::new();
+    let nb = 20;
+    for i in 0..nb {
+        for j in 0..20 {
+            let w = 100.0;
+            let x0 = -1000.0 + i as f64 * w;
+            let z0 = -1000.0 + j as f64 * w;
+            let y0 = 0.0;
+            let x1 = x0 + w;
+            let y1 = 100.0 * (rng.gen::<f64>() + 0.01);
+            let z1 = z0 + w;
+            box_list1.push(Arc::new(Cube::new(
+                Vector3::new(x0, y0, z0),
+                Vector3::new(x1, y1, z1),
+                ground.clone(),
+            )));
+        }
+    }
+    world.push(BVHNode::new(&mut box_list1, 0.0, 1.0));
+    let light = DiffuseLight::new(SolidTexture::new(7.0, 7.0, 7.0));
+    world.push(Rect::new(
+        Plane::ZX,
+        147.0,
+        412.0,
+        123.0,
+        423.0,
+        554.0,
+        light,
+    ));
+    let center = Vector3::new(400.0, 400.0, 200.0);
+    world.push(MovingSphere::new(
+        center,
+        center + Vector3::new(30.0, 0.0, 0.0),
+        0.0,
+        1.0,
+        50.0,
+        Lambertian::new(SolidTexture::new(0.7, 0.3, 0.1)),
+    ));
+    world.push(Sphere::new(
+        Vector3::new(260.0, 150.0, 45.0),
+        50.0,
+        Dielectric::new(1.5),
+    ));
+    world.push(Sphere::new(
+        Vector3::new(0.0, 150.0, 145.0),
+        50.0,
+        Metal::new(SolidTexture::new(0.8, 0.8, 0.9), 10.0),
+    ));
+    let boundary = Sphere::new(
+        Vector3::new(360.0, 150.0, 145.0),
+        70.0,
+        Dielectric::new(1.5),
+    );
+    world.push(boundary.clone());
+    world.push(ConstantMedium::new(
+        boundary,
+        0.2,
+        SolidTexture::new(0.2, 0.4, 0.9),
+    ));
+    let boundary = Sphere::new(Vector3::new(0.0, 0.0, 0.0), 5000.0, Dielectric::new(1.5));
+    world.push(ConstantMedium::new(
+        boundary,
+        0.0001,
+        SolidTexture::new(1.0, 1.0, 1.0),
+    ));
+    let texture = ImageTexture::open(EARTH_TEXTURE).expect("image not found");
+    world.push(Sphere::new(
+        Vector3::new(400.0, 200.0, 400.0),
+        100.0,
+        Lambertian::new(texture),
+    ));
+    world.push(Sphere::new(
+        Vector3::new(220.0, 280.0, 300.0),
+        80.0,
+        Lambertian::new(NoiseTexture::new(0.1)),
+    ));
+    let mut box_list2: Vec<Arc<dyn Hittable>> = Vec::new();
+    let ns = 1000;
+    for _ in 0..ns {
+        box_list2.push(Arc::new(Sphere::new(
+            Vector3::new(
+                165.0 * rng.gen::<f64>(),
+                165.0 * rng.gen::<f64>(),
+                165.0 * rng.gen::<f64>(),
+            ),
+            10.0,
+            white.clone(),
+        )));
+    }
+    world.push(Traslate::new(
+        Rotate::new(Axis::Y, BVHNode::new(&mut box_list2, 0.0, 0.1), 15.0),
+        Vector3::new(-100.0, 270.0, 395.0),
+    ));
+    Box::new(world)
+}
@@ -5,6 +5,8 @@ use crate::aabb::{self, AABB};
 use crate::hittable::{HitRecord, Hittable};
 use crate::material::Material;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::util::{orthonormal_basis, random_unit_vector};
 
 fn get_sphere_uv(p: &Vector3<f64>) -> (f64, f64) {
     let phi = p.z.atan2(p.x);
@@ -56,6 +58,7 @@ impl<M: Material> Hittable for Sphere<M> {
                     v,
                     normal,
                     material: &self.material,
+                    vertex_color: None,
                 });
             }
             let t = (-b + sqrt_discriminant) / a;
@@ -70,6 +73,7 @@ impl<M: Material> Hittable for Sphere<M> {
                     v,
                     normal,
                     material: &self.material,
+                    vertex_color: None,
                 });
             }
         }
@@ -82,6 +86,47 @@ impl<M: Material> Hittable for Sphere<M> {
         let max = self.center + radius;
         Some(AABB { min, max })
     }
+
+    // the directions of the cone that holds the sphere seen from origin are
+    // sampled uniformly
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Vector3<f64>) -> f64 {
+        if self
+            .hit(&Ray::new(*origin, *direction, 0.0), 0.001, f64::MAX)
+            .is_none()
+        {
+            return 0.0;
+        }
+        let distance2 = (self.center - origin).magnitude_squared();
+        let radius2 = self.radius * self.radius;
+        if distance2 <= radius2 {
+            return 1.0 / (4.0 * f64::consts::PI);
+        }
+        let cos_theta_max = (1.0 - radius2 / distance2).sqrt();
+        1.0 / (2.0 * f64::consts::PI * (1.0 - cos_theta_max))
+    }
+
+    fn random(&self, origin: &Vector3<f64>, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        let direction = self.center - origin;
+        let distance2 = direction.magnitude_squared();
+        let radius2 = self.radius * self.radius;
+        if distance2 <= radius2 {
+            return random_unit_vector(sampler);
+        }
+        let cos_theta_max = (1.0 - radius2 / distance2).sqrt();
+        let (r1, r2) = sampler.next_2d();
+        let z = 1.0 + r1 * (cos_theta_max - 1.0);
+        let phi = 2.0 * f64::consts::PI * r2;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let w = direction.normalize();
+        let (u, v) = orthonormal_basis(&w);
+        r * phi.cos() * u + r * phi.sin() * v + z * w
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        if self.material.is_emissive() {
+            lights.push(self);
+        }
+    }
 }
 
 pub struct MovingSphere<M: Material> {
@@ -142,6 +187,7 @@ impl<M: Material> Hittable for MovingSphere<M> {
                     v,
                     normal,
                     material: &self.material,
+                    vertex_color: None,
                 });
             }
             let t = (-b + sqrt_discriminant) / a;
@@ -156,6 +202,7 @@ impl<M: Material> Hittable for MovingSphere<M> {
                     v,
                     normal,
                     material: &self.material,
+                    vertex_color: None,
                 });
             }
         }
@@ -1,8 +1,27 @@
+use crate::hittable::HitRecord;
 use crate::perlin::Perlin;
 use nalgebra::Vector3;
+use std::path::Path;
+use std::sync::Arc;
 
-pub trait Texture {
+pub trait Texture: Send + Sync {
     fn value(&self, u: f64, v: f64, p: &Vector3<f64>) -> Vector3<f64>;
+
+    // the materials look up their textures at a hit with this, so a texture
+    // can use everything the object knows about the hit point
+    fn value_at(&self, hit: &HitRecord) -> Vector3<f64> {
+        self.value(hit.u, hit.v, &hit.p)
+    }
+}
+
+impl<T: Texture + ?Sized> Texture for Arc<T> {
+    fn value(&self, u: f64, v: f64, p: &Vector3<f64>) -> Vector3<f64> {
+        (**self).value(u, v, p)
+    }
+
+    fn value_at(&self, hit: &HitRecord) -> Vector3<f64> {
+        (**self).value_at(hit)
+    }
 }
 
 #[derive(Clone)]
@@ -81,6 +100,12 @@ impl ImageTexture {
     pub fn new(data: Vec<u8>, nx: u32, ny: u32) -> Self {
         Self { data, nx, ny }
     }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> image::ImageResult<Self> {
+        let image = image::open(path)?.to_rgb8();
+        let (nx, ny) = image.dimensions();
+        Ok(Self::new(image.into_raw(), nx, ny))
+    }
 }
 
 impl Texture for ImageTexture {
@@ -106,3 +131,29 @@ impl Texture for ImageTexture {
         Vector3::new(r, g, b)
     }
 }
+
+// the colors of the vertices of a mesh (e.g. from a scanned PLY model),
+// the fallback texture is used on objects that have no vertex colors
+#[derive(Clone)]
+pub struct VertexColorTexture<T: Texture> {
+    fallback: T,
+}
+
+impl<T: Texture> VertexColorTexture<T> {
+    pub fn new(fallback: T) -> Self {
+        Self { fallback }
+    }
+}
+
+impl<T: Texture> Texture for VertexColorTexture<T> {
+    fn value(&self, u: f64, v: f64, p: &Vector3<f64>) -> Vector3<f64> {
+        self.fallback.value(u, v, p)
+    }
+
+    fn value_at(&self, hit: &HitRecord) -> Vector3<f64> {
+        match hit.vertex_color {
+            Some(color) => color,
+            None => self.fallback.value_at(hit),
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use nalgebra::{Matrix3, Vector3};
+
+/*
+* From the linear radiance of the renderer to display colors: exposure,
+* a tone mapping operator that brings the radiance in [0, 1], and the sRGB
+* transfer function
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ToneMapOperator {
+    // hard clip of every channel
+    Clamp,
+    // L / (1 + L) on the luminance
+    Reinhard,
+    // Reinhard where the white point (ToneMapping::white) maps to 1
+    ExtendedReinhard,
+    // fit of the ACES reference and output transforms by Stephen Hill
+    Aces,
+    // polynomial fit of the AgX base look, keeps the hue of bright colors
+    AgX,
+}
+
+impl ToneMapOperator {
+    pub const NAMES: [&'static str; 5] = ["clamp", "reinhard", "extended_reinhard", "aces", "agx"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "clamp" => Some(ToneMapOperator::Clamp),
+            "reinhard" => Some(ToneMapOperator::Reinhard),
+            "extended_reinhard" => Some(ToneMapOperator::ExtendedReinhard),
+            "aces" => Some(ToneMapOperator::Aces),
+            "agx" => Some(ToneMapOperator::AgX),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneMapping {
+    // in stops, every stop doubles the radiance
+    pub exposure: f64,
+    pub operator: ToneMapOperator,
+    // the radiance that becomes white with ExtendedReinhard
+    pub white: f64,
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        Self {
+            exposure: 0.0,
+            operator: ToneMapOperator::Clamp,
+            white: 4.0,
+        }
+    }
+}
+
+impl ToneMapping {
+    // a linear color to an sRGB encoded color in [0, 1]
+    pub fn apply(&self, color: &Vector3<f64>) -> Vector3<f64> {
+        // NaN and negative radiance would poison the curves
+        let color = color.map(|c| if c > 0.0 { c } else { 0.0 }) * self.exposure.exp2();
+        let mapped = match self.operator {
+            ToneMapOperator::Clamp => color,
+            ToneMapOperator::Reinhard => scale_luminance(&color, |l| l / (1.0 + l)),
+            ToneMapOperator::ExtendedReinhard => {
+                let white2 = self.white * self.white;
+                scale_luminance(&color, |l| l * (1.0 + l / white2) / (1.0 + l))
+            }
+            ToneMapOperator::Aces => aces(&color),
+            ToneMapOperator::AgX => agx(&color),
+        };
+        mapped.map(|c| srgb_oetf(nalgebra::clamp(c, 0.0, 1.0)))
+    }
+}
+
+pub fn luminance(color: &Vector3<f64>) -> f64 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+// the sRGB opto-electronic transfer function, for linear values in [0, 1]
+pub fn srgb_oetf(c: f64) -> f64 {
+    if c <= 0.003_130_8 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// the inverse of srgb_oetf, from sRGB encoded values to linear
+pub fn srgb_eotf(c: f64) -> f64 {
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn scale_luminance<F: Fn(f64) -> f64>(color: &Vector3<f64>, curve: F) -> Vector3<f64> {
+    let l = luminance(color);
+    if l > 0.0 {
+        color * (curve(l) / l)
+    } else {
+        *color
+    }
+}
+
+fn aces(color: &Vector3<f64>) -> Vector3<f64> {
+    // sRGB to the ACES fit space, with the exposure of the reference
+    #[rustfmt::skip]
+    let input = Matrix3::new(
+        0.59719, 0.35458, 0.04823,
+        0.07600, 0.90834, 0.01566,
+        0.02840, 0.13383, 0.83777,
+    );
+    #[rustfmt::skip]
+    let output = Matrix3::new(
+        1.60475, -0.53108, -0.07367,
+        -0.10208, 1.10813, -0.00605,
+        -0.00327, -0.07276, 1.07602,
+    );
+    let v = input * color;
+    let a = v
+        .component_mul(&v.add_scalar(0.024_578_6))
+        .add_scalar(-0.000_090_537);
+    let b = v
+        .component_mul(&(0.983_729 * v).add_scalar(0.432_951))
+        .add_scalar(0.238_081);
+    output * a.component_div(&b)
+}
+
+fn agx(color: &Vector3<f64>) -> Vector3<f64> {
+    #[rustfmt::skip]
+    let inset = Matrix3::new(
+        0.842_479_062_253_094, 0.078_433_599_999_999_9, 0.079_223_745_147_994_4,
+        0.042_328_228_975_613_6, 0.878_468_636_469_772, 0.079_166_149_091_816_5,
+        0.042_375_654_357_164_6, 0.078_433_600_000_000_1, 0.879_142_973_793_104,
+    );
+    #[rustfmt::skip]
+    let outset = Matrix3::new(
+        1.196_879_005_120_17, -0.098_020_881_140_136_8, -0.099_029_744_079_720_3,
+        -0.052_896_851_757_456_2, 1.151_903_129_904_17, -0.098_961_176_844_843_2,
+        -0.052_971_635_514_443_3, -0.098_043_450_117_124_1, 1.151_073_672_641_16,
+    );
+    // log2 encoding of the exposure range [-12.47, 4.03] stops around middle gray
+    let min_ev = -12.473_931_188;
+    let max_ev = 4.026_068_812;
+    let v = (inset * color).map(|c| {
+        let ev = c.max(1e-10).log2();
+        nalgebra::clamp((ev - min_ev) / (max_ev - min_ev), 0.0, 1.0)
+    });
+    // sigmoid of the base look, its output is display encoded with gamma 2.2
+    let v = v.map(|x| {
+        let x2 = x * x;
+        let x4 = x2 * x2;
+        15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x
+            - 0.002_32
+    });
+    (outset * v).map(|c| c.max(0.0).powf(2.2))
+}
@@ -0,0 +1,150 @@
+use nalgebra::Vector3;
+use std::f64;
+
+use crate::aabb::AABB;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+
+// the value of the polynomial with the coefficients from the constant up
+fn evaluate(coefficients: &[f64], x: f64) -> f64 {
+    coefficients.iter().rev().fold(0.0, |acc, c| acc * x + c)
+}
+
+// the real roots between low and high, in order. the roots of the derivative
+// split the interval in pieces where the polynomial is monotonic, a piece
+// that changes sign holds one root found by bisection. a root where the
+// polynomial only touches 0 (a ray grazing the surface) is missed
+fn real_roots(coefficients: &[f64], low: f64, high: f64) -> Vec<f64> {
+    let degree = coefficients.len() - 1;
+    if degree == 1 {
+        let root = -coefficients[0] / coefficients[1];
+        return if root >= low && root <= high {
+            vec![root]
+        } else {
+            Vec::new()
+        };
+    }
+    let derivative: Vec<f64> = coefficients
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, c)| i as f64 * c)
+        .collect();
+    let mut bounds = vec![low];
+    bounds.extend(real_roots(&derivative, low, high));
+    bounds.push(high);
+
+    let mut roots = Vec::new();
+    for piece in bounds.windows(2) {
+        let (mut a, mut b) = (piece[0], piece[1]);
+        let (fa, fb) = (evaluate(coefficients, a), evaluate(coefficients, b));
+        if fa == 0.0 {
+            roots.push(a);
+            continue;
+        }
+        if fa * fb > 0.0 {
+            continue;
+        }
+        // until the two ends are next to each other
+        for _ in 0..100 {
+            let middle = 0.5 * (a + b);
+            if middle <= a || middle >= b {
+                break;
+            }
+            if (evaluate(coefficients, middle) > 0.0) == (fa > 0.0) {
+                a = middle;
+            } else {
+                b = middle;
+            }
+        }
+        roots.push(0.5 * (a + b));
+    }
+    roots.dedup();
+    roots
+}
+
+/*
+* A torus around the y axis: the circle of radius major in the plane y = 0
+* around center, thickened by minor. u goes around the axis, v around the
+* tube starting outside. Not sampled as a light
+*/
+pub struct Torus<M: Material> {
+    center: Vector3<f64>,
+    major: f64,
+    minor: f64,
+    material: M,
+}
+
+impl<M: Material> Torus<M> {
+    pub fn new(center: Vector3<f64>, major: f64, minor: f64, material: M) -> Self {
+        Self {
+            center,
+            major,
+            minor,
+            material,
+        }
+    }
+}
+
+impl<M: Material> Hittable for Torus<M> {
+    // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + z^2) is a quartic along the ray.
+    // it is solved from the point of the ray closest to the center, with a
+    // unit direction, so the coefficients stay small however far the ray starts
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let length = ray.direction().norm();
+        let d = ray.direction() / length;
+        let origin = ray.origin() - self.center;
+        let shift = -origin.dot(&d);
+        let o = origin + shift * d;
+
+        let outer = self.major + self.minor;
+        let q = o.dot(&o);
+        if q > outer * outer {
+            return None;
+        }
+        let r2 = self.major * self.major;
+        let e = q + r2 - self.minor * self.minor;
+        let coefficients = [
+            e * e - 4.0 * r2 * (o.x * o.x + o.z * o.z),
+            -8.0 * r2 * (o.x * d.x + o.z * d.z),
+            2.0 * e - 4.0 * r2 * (d.x * d.x + d.z * d.z),
+            0.0,
+            1.0,
+        ];
+        // the torus is inside the sphere of radius major + minor
+        let reach = (outer * outer - q).sqrt() * 1.001;
+        let t = real_roots(&coefficients, -reach, reach)
+            .into_iter()
+            .map(|s| (s + shift) / length)
+            .find(|&t| t > t_min && t < t_max)?;
+
+        let p = ray.pointing_at(t);
+        let local = p - self.center;
+        let radial = Vector3::new(local.x, 0.0, local.z);
+        let distance = radial.norm();
+        let ring = if distance > 0.0 {
+            radial * (self.major / distance)
+        } else {
+            Vector3::new(self.major, 0.0, 0.0)
+        };
+        let phi = (-local.z).atan2(local.x);
+        let theta = local.y.atan2(distance - self.major);
+        let turn = |angle: f64| angle.rem_euclid(2.0 * f64::consts::PI) / (2.0 * f64::consts::PI);
+        Some(HitRecord {
+            t,
+            u: turn(phi),
+            v: turn(theta),
+            p,
+            normal: (local - ring).normalize(),
+            material: &self.material,
+            vertex_color: None,
+        })
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<AABB> {
+        let outer = self.major + self.minor;
+        let extent = Vector3::new(outer, self.minor, outer);
+        Some(AABB::new(self.center - extent, self.center + extent))
+    }
+}
@@ -0,0 +1,314 @@
+use nalgebra::{Matrix3, Matrix4, Point3, Unit, UnitQuaternion, Vector3};
+
+use crate::aabb::{self, AABB};
+use crate::hittable::{self, HitRecord, Hittable};
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+
+// the matrices of the transformations, they compose by multiplication: in
+// b * a the transformation a is applied first
+pub fn translation(offset: &Vector3<f64>) -> Matrix4<f64> {
+    Matrix4::new_translation(offset)
+}
+
+// angle in degrees around an axis through the origin, counterclockwise when
+// the axis points toward the viewer
+pub fn rotation(axis: &Vector3<f64>, angle: f64) -> Matrix4<f64> {
+    Matrix4::from_axis_angle(&Unit::new_normalize(*axis), angle.to_radians())
+}
+
+pub fn scaling(scale: &Vector3<f64>) -> Matrix4<f64> {
+    Matrix4::new_nonuniform_scaling(scale)
+}
+
+/*
+* Any affine transformation of a hittable. The rays are moved into the space
+* of the hittable with the inverse matrix, the direction is not normalized so
+* the distances t stay the same. The hit points go back with the matrix and
+* the normals with the inverse transpose, which keeps them orthogonal to the
+* surface when the scale is not uniform
+*/
+pub struct Transform<H: Hittable> {
+    hittable: H,
+    matrix: Matrix4<f64>,
+    inverse: Matrix4<f64>,
+    normal_matrix: Matrix3<f64>,
+}
+
+impl<H: Hittable> Transform<H> {
+    // panics if the matrix cannot be inverted, e.g. a scale of 0
+    pub fn new(hittable: H, matrix: Matrix4<f64>) -> Self {
+        let inverse = matrix
+            .try_inverse()
+            .expect("the matrix of a transform must be invertible");
+        Self {
+            hittable,
+            matrix,
+            inverse,
+            normal_matrix: inverse.fixed_view::<3, 3>(0, 0).transpose(),
+        }
+    }
+
+    // the transformation followed by matrix, without wrapping it again
+    pub fn then(self, matrix: Matrix4<f64>) -> Self {
+        Self::new(self.hittable, matrix * self.matrix)
+    }
+
+    pub fn matrix(&self) -> &Matrix4<f64> {
+        &self.matrix
+    }
+
+    pub fn inverse(&self) -> &Matrix4<f64> {
+        &self.inverse
+    }
+
+    fn to_object(&self, p: &Vector3<f64>) -> Vector3<f64> {
+        self.inverse.transform_point(&Point3::from(*p)).coords
+    }
+}
+
+// the corners of the box, transformed, in a new box (Arvo, Transforming
+// Axis-Aligned Bounding Boxes): every coordinate of the new box sums the
+// smallest and the largest products of a row of the matrix
+pub fn transform_box(matrix: &Matrix4<f64>, bbox: &AABB) -> AABB {
+    let mut min = Vector3::new(matrix[(0, 3)], matrix[(1, 3)], matrix[(2, 3)]);
+    let mut max = min;
+    for i in 0..3 {
+        for j in 0..3 {
+            let a = matrix[(i, j)] * bbox.min[j];
+            let b = matrix[(i, j)] * bbox.max[j];
+            min[i] += a.min(b);
+            max[i] += a.max(b);
+        }
+    }
+    AABB::new(min, max)
+}
+
+impl<H: Hittable> Hittable for Transform<H> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let object_ray = Ray::new(
+            self.to_object(&ray.origin()),
+            self.inverse.transform_vector(&ray.direction()),
+            ray.time(),
+        );
+        self.hittable.hit(&object_ray, t_min, t_max).map(|mut hit| {
+            hit.p = self.matrix.transform_point(&Point3::from(hit.p)).coords;
+            hit.normal = (self.normal_matrix * hit.normal).normalize();
+            hit
+        })
+    }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<AABB> {
+        self.hittable
+            .bounding_box(t0, t1)
+            .map(|b| transform_box(&self.matrix, &b))
+    }
+
+    // a direction w of the hittable becomes A w / |A w| in the world, where A
+    // is the linear part of the matrix. the solid angles around it are
+    // multiplied by |det A| / |A w|^3 (the same change of variables as the
+    // linearly transformed cosines)
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Vector3<f64>) -> f64 {
+        let object_direction = self.inverse.transform_vector(direction);
+        let length = object_direction.norm() / direction.norm();
+        let determinant = self.inverse.fixed_view::<3, 3>(0, 0).determinant().abs();
+        self.hittable
+            .pdf_value(&self.to_object(origin), &object_direction)
+            * determinant
+            / length.powi(3)
+    }
+
+    fn random(&self, origin: &Vector3<f64>, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        let direction = self.hittable.random(&self.to_object(origin), sampler);
+        self.matrix.transform_vector(&direction)
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        if hittable::has_lights(&self.hittable) {
+            lights.push(self);
+        }
+    }
+}
+
+// the transformation of an animated hittable at a time: scale, then
+// rotation, then translation
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe {
+    pub time: f64,
+    pub translation: Vector3<f64>,
+    pub rotation: UnitQuaternion<f64>,
+    pub scale: Vector3<f64>,
+}
+
+impl Keyframe {
+    // no transformation
+    pub fn new(time: f64) -> Self {
+        Self {
+            time,
+            translation: Vector3::zeros(),
+            rotation: UnitQuaternion::identity(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    pub fn matrix(&self) -> Matrix4<f64> {
+        translation(&self.translation) * self.rotation.to_homogeneous() * scaling(&self.scale)
+    }
+
+    // the translation and the scale change linearly, the rotation at a
+    // constant angular speed along the shortest arc (slerp)
+    fn lerp(&self, other: &Keyframe, time: f64) -> Keyframe {
+        let s = if other.time > self.time {
+            (time - self.time) / (other.time - self.time)
+        } else {
+            0.0
+        };
+        Keyframe {
+            time,
+            translation: self.translation.lerp(&other.translation, s),
+            // half a turn apart the arc is ambiguous, any of them will do
+            rotation: self
+                .rotation
+                .try_slerp(&other.rotation, s, 1e-9)
+                .unwrap_or_else(|| self.rotation.nlerp(&other.rotation, s)),
+            scale: self.scale.lerp(&other.scale, s),
+        }
+    }
+
+    fn transform_point(&self, p: &Vector3<f64>) -> Vector3<f64> {
+        self.rotation * p.component_mul(&self.scale) + self.translation
+    }
+
+    fn inverse_transform_point(&self, p: &Vector3<f64>) -> Vector3<f64> {
+        self.inverse_transform_vector(&(p - self.translation))
+    }
+
+    fn inverse_transform_vector(&self, v: &Vector3<f64>) -> Vector3<f64> {
+        (self.rotation.inverse() * v).component_div(&self.scale)
+    }
+}
+
+/*
+* A hittable that moves during the exposure, for motion blur. The keyframes
+* are interpolated at the time of every ray, before the first and after the
+* last one the hittable stays still. The bounding box covers the whole motion
+* between t0 and t1, so a BVH built around it still finds every hit.
+* The lights inside are not sampled by the next-event estimation: the
+* density of a direction depends on the time, which the light sampling does
+* not know. the rays that hit them still carry their emission
+*/
+pub struct AnimatedTransform<H: Hittable> {
+    hittable: H,
+    keyframes: Vec<Keyframe>,
+}
+
+impl<H: Hittable> AnimatedTransform<H> {
+    // panics without keyframes or with a scale of 0
+    pub fn new(hittable: H, mut keyframes: Vec<Keyframe>) -> Self {
+        assert!(!keyframes.is_empty(), "an animation needs a keyframe");
+        assert!(
+            keyframes.iter().all(|k| k.scale.iter().all(|&s| s != 0.0)),
+            "the scale of a keyframe cannot be 0"
+        );
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Self {
+            hittable,
+            keyframes,
+        }
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    // the keyframes interpolated at time
+    pub fn at(&self, time: f64) -> Keyframe {
+        let next = self.keyframes.partition_point(|k| k.time <= time);
+        if next == 0 {
+            Keyframe {
+                time,
+                ..self.keyframes[0]
+            }
+        } else if next == self.keyframes.len() {
+            Keyframe {
+                time,
+                ..self.keyframes[next - 1]
+            }
+        } else {
+            self.keyframes[next - 1].lerp(&self.keyframes[next], time)
+        }
+    }
+}
+
+// the box swept by bbox between two keyframes that are interpolated linearly.
+// every corner of the box moves along a segment when the rotation does not
+// change, so the box of the two ends covers it. when the rotation changes the
+// corners stay inside a sphere around the origin of the object, the sphere
+// moves with the translation
+fn swept_box(bbox: &AABB, start: &Keyframe, end: &Keyframe) -> AABB {
+    let corner = |i: usize| {
+        Vector3::new(
+            if i & 1 == 0 { bbox.min.x } else { bbox.max.x },
+            if i & 2 == 0 { bbox.min.y } else { bbox.max.y },
+            if i & 4 == 0 { bbox.min.z } else { bbox.max.z },
+        )
+    };
+    let ends = if start.rotation.angle_to(&end.rotation) < 1e-12 {
+        [start, end]
+            .iter()
+            .map(|k| transform_box(&k.matrix(), bbox))
+            .collect::<Vec<AABB>>()
+    } else {
+        let radius = (0..8)
+            .map(|i| {
+                let c = corner(i);
+                c.component_mul(&start.scale)
+                    .norm()
+                    .max(c.component_mul(&end.scale).norm())
+            })
+            .fold(0.0, f64::max);
+        [start, end]
+            .iter()
+            .map(|k| {
+                let r = Vector3::repeat(radius);
+                AABB::new(k.translation - r, k.translation + r)
+            })
+            .collect()
+    };
+    aabb::surrounding_box(&ends[0], &ends[1])
+}
+
+impl<H: Hittable> Hittable for AnimatedTransform<H> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let keyframe = self.at(ray.time());
+        let object_ray = Ray::new(
+            keyframe.inverse_transform_point(&ray.origin()),
+            keyframe.inverse_transform_vector(&ray.direction()),
+            ray.time(),
+        );
+        self.hittable.hit(&object_ray, t_min, t_max).map(|mut hit| {
+            hit.p = keyframe.transform_point(&hit.p);
+            // the inverse transpose of rotation * scale
+            hit.normal =
+                (keyframe.rotation * hit.normal.component_div(&keyframe.scale)).normalize();
+            hit
+        })
+    }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<AABB> {
+        let bbox = self.hittable.bounding_box(t0, t1)?;
+        // the keyframes inside the interval split it in linear pieces
+        let mut times = vec![t0];
+        times.extend(
+            self.keyframes
+                .iter()
+                .map(|k| k.time)
+                .filter(|&t| t > t0 && t < t1),
+        );
+        times.push(t1);
+        times
+            .windows(2)
+            .map(|w| swept_box(&bbox, &self.at(w[0]), &self.at(w[1])))
+            .reduce(|a, b| aabb::surrounding_box(&a, &b))
+    }
+}
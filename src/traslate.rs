@@ -1,6 +1,7 @@
 use crate::aabb::AABB;
-use crate::hittable::{HitRecord, Hittable};
+use crate::hittable::{self, HitRecord, Hittable};
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use nalgebra::Vector3;
 
 pub struct Traslate<H: Hittable> {
@@ -30,4 +31,19 @@ impl<H: Hittable> Hittable for Traslate<H> {
             b
         })
     }
+
+    // a translation does not change the directions
+    fn pdf_value(&self, origin: &Vector3<f64>, direction: &Vector3<f64>) -> f64 {
+        self.hitable.pdf_value(&(origin - self.offset), direction)
+    }
+
+    fn random(&self, origin: &Vector3<f64>, sampler: &mut dyn Sampler) -> Vector3<f64> {
+        self.hitable.random(&(origin - self.offset), sampler)
+    }
+
+    fn collect_lights<'a>(&'a self, lights: &mut Vec<&'a dyn Hittable>) {
+        if hittable::has_lights(&self.hitable) {
+            lights.push(self);
+        }
+    }
 }
@@ -0,0 +1,137 @@
+use nalgebra::{Vector2, Vector3};
+
+use crate::aabb::AABB;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+
+// Möller-Trumbore intersection, returns the distance of the hit and the
+// barycentric coordinates of the second and third vertex
+pub(crate) fn intersect(
+    ray: &Ray,
+    vertices: &[Vector3<f64>; 3],
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, f64, f64)> {
+    let edge1 = vertices[1] - vertices[0];
+    let edge2 = vertices[2] - vertices[0];
+    let pvec = ray.direction().cross(&edge2);
+    let det = edge1.dot(&pvec);
+    if det.abs() < 1e-12 {
+        // the ray is parallel to the triangle
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let tvec = ray.origin() - vertices[0];
+    let b1 = tvec.dot(&pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+    let qvec = tvec.cross(&edge1);
+    let b2 = ray.direction().dot(&qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+    let t = edge2.dot(&qvec) * inv_det;
+    if t < t_max && t > t_min {
+        Some((t, b1, b2))
+    } else {
+        None
+    }
+}
+
+pub(crate) fn bounding_box(vertices: &[Vector3<f64>; 3]) -> AABB {
+    let mut min = vertices[0].inf(&vertices[1]).inf(&vertices[2]);
+    let mut max = vertices[0].sup(&vertices[1]).sup(&vertices[2]);
+    // a triangle lying on an axis aligned plane still needs a thick box
+    for a in 0..3 {
+        if max[a] - min[a] < 0.0002 {
+            min[a] -= 0.0001;
+            max[a] += 0.0001;
+        }
+    }
+    AABB { min, max }
+}
+
+pub(crate) fn interpolate<T>(values: &[T; 3], b1: f64, b2: f64) -> T
+where
+    T: Copy + std::ops::Mul<f64, Output = T> + std::ops::Add<Output = T>,
+{
+    values[0] * (1.0 - b1 - b2) + values[1] * b1 + values[2] * b2
+}
+
+// the normal follows the winding of the vertices (counter clockwise seen
+// from the front), per vertex normals are interpolated when available
+pub(crate) fn shading_normal(
+    vertices: &[Vector3<f64>; 3],
+    normals: Option<&[Vector3<f64>; 3]>,
+    b1: f64,
+    b2: f64,
+) -> Vector3<f64> {
+    match normals {
+        Some(normals) => interpolate(normals, b1, b2).normalize(),
+        None => (vertices[1] - vertices[0])
+            .cross(&(vertices[2] - vertices[0]))
+            .normalize(),
+    }
+}
+
+// without texture coordinates the barycentric coordinates are used as uv
+pub(crate) fn texture_uv(uvs: Option<&[Vector2<f64>; 3]>, b1: f64, b2: f64) -> (f64, f64) {
+    match uvs {
+        Some(uvs) => {
+            let uv = interpolate(uvs, b1, b2);
+            (uv.x, uv.y)
+        }
+        None => (b1, b2),
+    }
+}
+
+pub struct Triangle<M: Material> {
+    vertices: [Vector3<f64>; 3],
+    normals: Option<[Vector3<f64>; 3]>,
+    uvs: Option<[Vector2<f64>; 3]>,
+    material: M,
+}
+
+impl<M: Material> Triangle<M> {
+    pub fn new(v0: Vector3<f64>, v1: Vector3<f64>, v2: Vector3<f64>, material: M) -> Self {
+        Self {
+            vertices: [v0, v1, v2],
+            normals: None,
+            uvs: None,
+            material,
+        }
+    }
+
+    pub fn with_normals(mut self, normals: [Vector3<f64>; 3]) -> Self {
+        self.normals = Some(normals);
+        self
+    }
+
+    pub fn with_uvs(mut self, uvs: [Vector2<f64>; 3]) -> Self {
+        self.uvs = Some(uvs);
+        self
+    }
+}
+
+impl<M: Material> Hittable for Triangle<M> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        intersect(ray, &self.vertices, t_min, t_max).map(|(t, b1, b2)| {
+            let (u, v) = texture_uv(self.uvs.as_ref(), b1, b2);
+            HitRecord {
+                t,
+                u,
+                v,
+                p: ray.pointing_at(t),
+                normal: shading_normal(&self.vertices, self.normals.as_ref(), b1, b2),
+                material: &self.material,
+                vertex_color: None,
+            }
+        })
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<AABB> {
+        Some(bounding_box(&self.vertices))
+    }
+}
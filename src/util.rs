@@ -1,24 +1,54 @@
 use nalgebra::Vector3;
-use rand::Rng;
+use std::f64;
 
-pub fn random_in_unit_sphere() -> Vector3<f64> {
-    let mut rng = rand::thread_rng();
-    let unit = Vector3::new(1.0, 1.0, 1.0);
-    loop {
-        let p = 2.0 * Vector3::new(rng.gen::<f64>(), rng.gen::<f64>(), rng.gen::<f64>()) - unit;
-        if p.magnitude_squared() < 1.0 {
-            return p;
-        }
-    }
+use crate::sampler::Sampler;
+
+// the directions come from closed forms instead of rejection loops, so every
+// call takes the same numbers from the sampler
+
+pub fn random_in_unit_sphere(sampler: &mut dyn Sampler) -> Vector3<f64> {
+    let direction = random_unit_vector(sampler);
+    direction * sampler.next_1d().cbrt()
+}
+
+pub fn random_in_unit_disk(sampler: &mut dyn Sampler) -> Vector3<f64> {
+    let (u1, u2) = sampler.next_2d();
+    let r = u1.sqrt();
+    let phi = 2.0 * f64::consts::PI * u2;
+    Vector3::new(r * phi.cos(), r * phi.sin(), 0.0)
+}
+
+// uniform on the unit sphere
+pub fn random_unit_vector(sampler: &mut dyn Sampler) -> Vector3<f64> {
+    let (u1, u2) = sampler.next_2d();
+    let z = 2.0 * u1 - 1.0;
+    let phi = 2.0 * f64::consts::PI * u2;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    Vector3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+// two unit vectors that form an orthonormal basis with the unit vector w
+pub fn orthonormal_basis(w: &Vector3<f64>) -> (Vector3<f64>, Vector3<f64>) {
+    let a = if w.x.abs() > 0.9 {
+        Vector3::new(0.0, 1.0, 0.0)
+    } else {
+        Vector3::new(1.0, 0.0, 0.0)
+    };
+    let v = w.cross(&a).normalize();
+    let u = w.cross(&v);
+    (u, v)
 }
 
-pub fn random_in_unit_disk() -> Vector3<f64> {
-    let mut rng = rand::thread_rng();
-    let unit = Vector3::new(1.0, 1.0, 0.0);
-    loop {
-        let p = 2.0 * Vector3::new(rng.gen::<f64>(), rng.gen::<f64>(), 0.0) - unit;
-        if p.magnitude_squared() < 1.0 {
-            return p;
-        }
+// the real roots of a t^2 + 2 half_b t + c, the smallest first
+pub fn quadratic_roots(a: f64, half_b: f64, c: f64) -> Option<(f64, f64)> {
+    let discriminant = half_b * half_b - a * c;
+    if a == 0.0 || discriminant < 0.0 {
+        return None;
     }
+    let sqrt_discriminant = discriminant.sqrt();
+    let (t0, t1) = (
+        (-half_b - sqrt_discriminant) / a,
+        (-half_b + sqrt_discriminant) / a,
+    );
+    Some((t0.min(t1), t0.max(t1)))
 }
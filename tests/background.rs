@@ -0,0 +1,264 @@
+use raytracing_in_rust::*;
+
+use nalgebra::Vector3;
+use std::sync::Arc;
+
+use crate::background::{Background, EnvironmentMap, Gradient};
+use crate::camera::CameraSettings;
+use crate::color::{color, PathDepth};
+use crate::hittable::HittableList;
+use crate::material::Lambertian;
+use crate::output::{self, OutputFormat};
+use crate::ray::Ray;
+use crate::rect::{Plane, Rect};
+use crate::renderer::{FrameBuffer, RenderSettings, Renderer};
+use crate::sampler::IndependentSampler;
+use crate::texture::SolidTexture;
+use crate::tonemap::ToneMapping;
+
+fn ray(direction: Vector3<f64>) -> Ray {
+    Ray::new(Vector3::zeros(), direction, 0.0)
+}
+
+// 4x2 panorama, every pixel has its own color
+fn panorama() -> EnvironmentMap {
+    let pixels = (0..8)
+        .map(|i| Vector3::new(i as f64, 10.0 - i as f64, 1.0))
+        .collect();
+    EnvironmentMap::new(4, 2, pixels)
+}
+
+#[test]
+fn test_constant_and_gradient() {
+    let color = Vector3::new(0.1, 0.2, 0.3);
+    assert_eq!(color.radiance(&ray(Vector3::new(1.0, 2.0, 3.0))), color);
+
+    let sky = Gradient::sky();
+    let up = sky.radiance(&ray(Vector3::new(0.0, 5.0, 0.0)));
+    let down = sky.radiance(&ray(Vector3::new(0.0, -0.1, 0.0)));
+    let horizon = sky.radiance(&ray(Vector3::new(1.0, 0.0, 0.0)));
+    assert!((up - sky.top).norm() < 1e-12);
+    assert!((down - sky.bottom).norm() < 1e-12);
+    assert!((horizon - (sky.top + sky.bottom) / 2.0).norm() < 1e-12);
+}
+
+#[test]
+fn test_environment_map_projection() {
+    let mut map = panorama();
+    // -z is in the middle of the image, the upper row is above the horizon
+    assert_eq!(map.pixel_at(&Vector3::new(-0.01, 0.5, -1.0)), (1, 0));
+    assert_eq!(map.pixel_at(&Vector3::new(0.01, -0.5, -1.0)), (2, 1));
+    assert_eq!(map.pixel_at(&Vector3::new(-0.01, 0.5, 1.0)), (0, 0));
+    assert_eq!(map.pixel_at(&Vector3::new(0.01, 0.5, 1.0)), (3, 0));
+    assert_eq!(
+        map.radiance(&ray(Vector3::new(0.01, -0.5, -1.0))),
+        map.get(2, 1)
+    );
+
+    for &rotation in [0.0, 90.0, -200.0].iter() {
+        map.rotation = rotation;
+        for j in 0..2 {
+            for i in 0..4 {
+                let u = (i as f64 + 0.5) / 4.0;
+                let v = (j as f64 + 0.5) / 2.0;
+                let direction = map.direction(u, v);
+                assert!((direction.norm() - 1.0).abs() < 1e-12);
+                assert_eq!(map.pixel_at(&direction), (i, j));
+            }
+        }
+    }
+    // a quarter turn brings the pixels from -z to +x
+    map.rotation = 90.0;
+    assert_eq!(map.pixel_at(&Vector3::new(1.0, 0.5, -0.01)), (1, 0));
+
+    map.intensity = 2.0;
+    assert_eq!(map.get(3, 1), Vector3::new(14.0, 6.0, 2.0));
+}
+
+#[test]
+fn test_environment_map_files() {
+    let mut frame = FrameBuffer::new(2, 1);
+    frame.set(0, 0, Vector3::new(8.0, 0.5, 0.25));
+    frame.set(1, 0, Vector3::new(0.25, 0.25, 0.25));
+
+    let path = std::env::temp_dir().join("raytracing_background.hdr");
+    output::save(&frame, &path, OutputFormat::Hdr, &ToneMapping::default()).unwrap();
+    let map = EnvironmentMap::open(&path).unwrap();
+    assert_eq!((map.width(), map.height()), (2, 1));
+    assert!((map.get(0, 0) - frame.get(0, 0)).norm() < 0.05);
+
+    // 8 bit images are sRGB
+    let path = std::env::temp_dir().join("raytracing_background.png");
+    output::save(&frame, &path, OutputFormat::Png, &ToneMapping::default()).unwrap();
+    let map = EnvironmentMap::open(&path).unwrap();
+    assert!((map.get(1, 0) - frame.get(1, 0)).norm() < 0.005);
+
+    assert!(EnvironmentMap::open("no/such/sky.hdr").is_err());
+}
+
+#[test]
+fn test_render_and_scene_backgrounds() {
+    let settings = RenderSettings {
+        width: 4,
+        height: 2,
+        samples: 1,
+        ..Default::default()
+    };
+    let camera = CameraSettings::default().build(settings.aspect_ratio());
+    let world = HittableList::default();
+    let frame =
+        Renderer::with_background(settings, Arc::new(Gradient::sky())).render(&camera, &world);
+    // brighter toward the bottom, bluer toward the top
+    assert!(frame.get(0, 1).x > frame.get(0, 0).x);
+
+    let small = "settings { width 4 height 2 samples 1 }\n";
+    let scene = scene::parse(&format!(
+        "{}background gradient {{ top 0 0 1 bottom 1 0 0 }}",
+        small
+    ))
+    .unwrap();
+    let frame = scene
+        .renderer()
+        .render(&scene.camera(), scene.world.as_ref());
+    assert!(frame.get(0, 0).z > frame.get(0, 0).x);
+    assert!(frame.get(0, 1).x > frame.get(0, 1).z);
+
+    let scene = scene::parse(&format!(
+        "{}background constant {{ color 0.5 0.5 0.5 }}",
+        small
+    ))
+    .unwrap();
+    let frame = scene
+        .renderer()
+        .render(&scene.camera(), scene.world.as_ref());
+    assert_eq!(frame.get(1, 1), Vector3::new(0.5, 0.5, 0.5));
+    assert!(scene::parse("settings { }").unwrap().background.is_none());
+
+    let e = scene::parse("background environment { file \"missing.hdr\" }")
+        .err()
+        .unwrap();
+    assert_eq!((e.line, e.column), (1, 31));
+    let e = scene::parse("background cube { }").err().unwrap();
+    assert!(e.message.contains("unknown background kind"));
+}
+
+// a dim sky with a sun in the pixel (5, 1)
+fn sun(strength: f64) -> EnvironmentMap {
+    let mut pixels = vec![Vector3::new(0.2, 0.2, 0.2); 16 * 8];
+    pixels[16 + 5] = Vector3::new(strength, strength, strength);
+    EnvironmentMap::new(16, 8, pixels)
+}
+
+#[test]
+fn test_environment_map_sampling() {
+    let mut map = sun(500.0);
+    assert!(map.is_light());
+    assert!(!EnvironmentMap::new(2, 1, vec![Vector3::zeros(); 2]).is_light());
+    assert!(!Gradient::sky().is_light());
+
+    for &rotation in [0.0, 135.0].iter() {
+        map.rotation = rotation;
+        // the density is constant over a pixel, it sums to 1 over the solid
+        // angles of the pixels
+        let mut integral = 0.0;
+        for j in 0..8 {
+            let theta0 = j as f64 / 8.0 * std::f64::consts::PI;
+            let theta1 = (j + 1) as f64 / 8.0 * std::f64::consts::PI;
+            let solid_angle = 2.0 * std::f64::consts::PI / 16.0 * (theta0.cos() - theta1.cos());
+            for i in 0..16 {
+                let direction = map.direction((i as f64 + 0.5) / 16.0, (j as f64 + 0.5) / 8.0);
+                integral += map.pdf_value(&direction) * solid_angle;
+            }
+        }
+        assert!((integral - 1.0).abs() < 1e-9, "{}", integral);
+
+        // the sun holds most of the power, wherever it is turned
+        let mut sampler = IndependentSampler::new(1);
+        let in_sun = (0..1000)
+            .map(|_| map.random(&mut sampler))
+            .filter(|d| map.pdf_value(d) > 0.0 && map.pixel_at(d) == (5, 1))
+            .count();
+        assert!(in_sun > 800, "{}", in_sun);
+    }
+}
+
+// hides the sampling of a background, so only the material finds it
+struct Unsampled(EnvironmentMap);
+
+impl Background for Unsampled {
+    fn radiance(&self, ray: &Ray) -> Vector3<f64> {
+        self.0.radiance(ray)
+    }
+}
+
+// a floor lit by the sky: the radiance leaving it is albedo / pi times the
+// sum over the pixels above the horizon of L * (2 pi / width) *
+// (sin^2 theta1 - sin^2 theta0) / 2
+#[test]
+fn test_environment_lighting_is_unbiased_and_less_noisy() {
+    let map = sun(50.0);
+    let mut expected = 0.0;
+    for j in 0..4 {
+        let theta0 = j as f64 / 8.0 * std::f64::consts::PI;
+        let theta1 = (j + 1) as f64 / 8.0 * std::f64::consts::PI;
+        for i in 0..16 {
+            expected += map.get(i, j).x
+                * (2.0 * std::f64::consts::PI / 16.0)
+                * (theta1.sin().powi(2) - theta0.sin().powi(2))
+                / 2.0;
+        }
+    }
+    expected *= 0.5 / std::f64::consts::PI;
+
+    let mut world = HittableList::default();
+    world.push(Rect::new(
+        Plane::ZX,
+        -1000.0,
+        -1000.0,
+        1000.0,
+        1000.0,
+        0.0,
+        Lambertian::new(SolidTexture::new(0.5, 0.5, 0.5)),
+    ));
+    let ray = Ray::new(
+        Vector3::new(-1.0, 1.0, -0.5),
+        Vector3::new(1.0, -1.0, 0.5),
+        0.0,
+    );
+    let n = 40_000;
+    let stats = |background: &dyn Background| {
+        let mut sampler = IndependentSampler::new(1);
+        let samples: Vec<f64> = (0..n)
+            .map(|_| {
+                color(
+                    &ray,
+                    &world,
+                    &[],
+                    background,
+                    &PathDepth::new(1),
+                    &mut sampler,
+                )
+                .x
+            })
+            .collect();
+        let mean = samples.iter().sum::<f64>() / n as f64;
+        let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n as f64;
+        (mean, variance)
+    };
+
+    let (sampled_mean, sampled_variance) = stats(&map);
+    let (unsampled_mean, unsampled_variance) = stats(&Unsampled(map.clone()));
+    assert!(
+        (sampled_mean - expected).abs() < 0.02,
+        "{} {}",
+        sampled_mean,
+        expected
+    );
+    assert!(
+        (unsampled_mean - expected).abs() < 0.06,
+        "{} {}",
+        unsampled_mean,
+        expected
+    );
+    assert!(sampled_variance < unsampled_variance / 4.0);
+}
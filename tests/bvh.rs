@@ -0,0 +1,170 @@
+use raytracing_in_rust::*;
+
+mod common;
+
+use nalgebra::Vector3;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
+use std::thread;
+
+use crate::bvh::BVHNode;
+use crate::camera::Camera;
+use crate::color::PathDepth;
+use crate::common::gray;
+use crate::hittable::{Hittable, HittableList};
+use crate::material::Lambertian;
+use crate::ray::Ray;
+use crate::rect::{Plane, Rect};
+use crate::renderer::{RenderSettings, Renderer};
+use crate::sphere::Sphere;
+use crate::texture::SolidTexture;
+
+fn random_spheres(rng: &mut StdRng, count: usize) -> Vec<Arc<dyn Hittable>> {
+    let mut spheres: Vec<Arc<dyn Hittable>> = Vec::new();
+    for _ in 0..count {
+        let center = Vector3::new(
+            rng.gen_range(-10.0..10.0),
+            rng.gen_range(-10.0..10.0),
+            rng.gen_range(-10.0..10.0),
+        );
+        spheres.push(Arc::new(Sphere::new(
+            center,
+            rng.gen_range(0.1..1.0),
+            Lambertian::new(SolidTexture::new(0.5, 0.5, 0.5)),
+        )));
+    }
+    spheres
+}
+
+fn random_ray(rng: &mut StdRng) -> Ray {
+    let origin = Vector3::new(
+        rng.gen_range(-15.0..15.0),
+        rng.gen_range(-15.0..15.0),
+        rng.gen_range(-15.0..15.0),
+    );
+    let target = Vector3::new(
+        rng.gen_range(-5.0..5.0),
+        rng.gen_range(-5.0..5.0),
+        rng.gen_range(-5.0..5.0),
+    );
+    Ray::new(origin, target - origin, 0.0)
+}
+
+#[test]
+fn test_bvh_matches_list() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let spheres = random_spheres(&mut rng, 200);
+    let mut list = HittableList::default();
+    for sphere in spheres.iter() {
+        list.push(sphere.clone());
+    }
+    let bvh = BVHNode::from_list(spheres, 0.0, 1.0);
+
+    for _ in 0..2000 {
+        let ray = random_ray(&mut rng);
+        let expected = list.hit(&ray, 0.001, f64::MAX).map(|hit| hit.t);
+        let found = bvh.hit(&ray, 0.001, f64::MAX).map(|hit| hit.t);
+        assert_eq!(expected, found);
+    }
+}
+
+#[test]
+fn test_bvh_shared_between_threads() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let bvh = Arc::new(BVHNode::from_list(random_spheres(&mut rng, 100), 0.0, 1.0));
+
+    let workers: Vec<_> = (0..4)
+        .map(|seed| {
+            let bvh = bvh.clone();
+            thread::spawn(move || {
+                let mut rng = StdRng::seed_from_u64(seed);
+                (0..500)
+                    .filter(|_| bvh.hit(&random_ray(&mut rng), 0.001, f64::MAX).is_some())
+                    .count()
+            })
+        })
+        .collect();
+    for worker in workers {
+        assert!(worker.join().unwrap() > 0);
+    }
+
+    // the same tree is reused by two renders and by a scene list
+    let settings = RenderSettings {
+        width: 4,
+        height: 4,
+        samples: 1,
+        depth: PathDepth::new(2),
+        ..Default::default()
+    };
+    let camera = Camera::new(
+        Vector3::new(0.0, 0.0, 30.0),
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+        40.0,
+        settings.aspect_ratio(),
+        0.0,
+        30.0,
+        0.0,
+        1.0,
+    );
+    let mut world = HittableList::default();
+    world.push(bvh.clone());
+    let renderer = Renderer::new(settings);
+    assert_eq!(renderer.render(&camera, &world).pixels().len(), 16);
+    assert_eq!(renderer.render(&camera, &bvh).pixels().len(), 16);
+    assert_eq!(Arc::strong_count(&bvh), 2);
+}
+
+#[test]
+fn test_sah_build_is_deterministic() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let spheres = random_spheres(&mut rng, 300);
+    let first = BVHNode::from_list(spheres.clone(), 0.0, 1.0).stats();
+    let second = BVHNode::from_list(spheres, 0.0, 1.0).stats();
+
+    assert_eq!(first, second);
+    // leaves hold up to four spheres, every interior node has two children
+    assert!(first.leaf_count >= 75 && first.leaf_count <= 300);
+    assert_eq!(first.node_count, 2 * first.leaf_count - 1);
+    assert!(first.depth >= 7 && first.depth < 40);
+    // a ray that visits every primitive would cost 300
+    assert!(first.cost > 1.0 && first.cost < 60.0);
+}
+
+// the rects of every plane get a box on their own axes, thin along the
+// third one, so a BVH built around them still finds them
+#[test]
+fn test_rect_boxes_follow_their_plane() {
+    let yz = Rect::new(Plane::YZ, 1.0, 2.0, 3.0, 4.0, 5.0, gray());
+    let bbox = yz.bounding_box(0.0, 1.0).unwrap();
+    assert!((bbox.min - Vector3::new(5.0, 1.0, 2.0)).abs().max() <= 0.0001);
+    assert!((bbox.max - Vector3::new(5.0, 3.0, 4.0)).abs().max() <= 0.0001);
+    assert!(bbox.min.x < 5.0 && bbox.max.x > 5.0);
+
+    let zx = Rect::new(Plane::ZX, 1.0, 2.0, 3.0, 4.0, 5.0, gray());
+    let bbox = zx.bounding_box(0.0, 1.0).unwrap();
+    assert!((bbox.min - Vector3::new(2.0, 5.0, 1.0)).abs().max() <= 0.0001);
+    assert!((bbox.max - Vector3::new(4.0, 5.0, 3.0)).abs().max() <= 0.0001);
+    assert!(bbox.min.y < 5.0 && bbox.max.y > 5.0);
+
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut objects = random_spheres(&mut rng, 20);
+    objects.push(Arc::new(yz));
+    objects.push(Arc::new(zx));
+    let bvh = BVHNode::from_list(objects, 0.0, 1.0);
+    let ray = Ray::new(
+        Vector3::new(20.0, 2.0, 3.0),
+        Vector3::new(-1.0, 0.0, 0.0),
+        0.0,
+    );
+    let t = bvh.hit(&ray, 0.001, f64::MAX).unwrap().t;
+    assert!((t - 15.0).abs() < 1e-9);
+    let ray = Ray::new(
+        Vector3::new(3.0, 20.0, 2.0),
+        Vector3::new(0.0, -1.0, 0.0),
+        0.0,
+    );
+    let t = bvh.hit(&ray, 0.001, f64::MAX).unwrap().t;
+    assert!((t - 15.0).abs() < 1e-9);
+}
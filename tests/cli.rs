@@ -0,0 +1,82 @@
+use std::process::Command;
+
+fn binary() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_raytracing-in-rust"))
+}
+
+#[test]
+fn test_render_scene_file() {
+    let output = std::env::temp_dir().join("raytracing_cli_cornell.ppm");
+    let _ = std::fs::remove_file(&output);
+    let status = binary()
+        .args(["scene/cornell_box.scene", "--width", "16", "--spp", "2"])
+        .args([
+            "--depth",
+            "3",
+            "--threads",
+            "2",
+            "--seed",
+            "7",
+            "--sampler",
+            "stratified",
+            "--quiet",
+            "-o",
+        ])
+        .arg(&output)
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(0));
+    // the height follows the aspect ratio of the scene
+    let image = std::fs::read_to_string(&output).unwrap();
+    assert!(image.starts_with("P3\n16 16\n255\n"));
+    assert_eq!(image.lines().count(), 3 + 16 * 16);
+}
+
+#[test]
+fn test_render_built_in_scene() {
+    let output = std::env::temp_dir().join("raytracing_cli_two_spheres.ppm");
+    let status = binary()
+        .args(["two_spheres", "-w", "8", "-H", "4", "-s", "1", "-q", "-o"])
+        .arg(&output)
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(0));
+    let image = std::fs::read_to_string(&output).unwrap();
+    assert!(image.starts_with("P3\n8 4\n255\n"));
+
+    let list = binary().arg("--list").output().unwrap();
+    assert!(String::from_utf8(list.stdout)
+        .unwrap()
+        .contains("cornell_smoke"));
+}
+
+#[test]
+fn test_exit_codes() {
+    let code = |args: &[&str]| binary().args(args).output().unwrap().status.code();
+    assert_eq!(code(&["--help"]), Some(0));
+    assert_eq!(code(&[]), Some(2));
+    assert_eq!(code(&["scene/cornell_box.scene", "--spp"]), Some(2));
+    assert_eq!(
+        code(&["scene/cornell_box.scene", "--width", "wide"]),
+        Some(2)
+    );
+    assert_eq!(
+        code(&["scene/cornell_box.scene", "--bounces", "3"]),
+        Some(2)
+    );
+    assert_eq!(
+        code(&["scene/cornell_box.scene", "--sampler", "random"]),
+        Some(2)
+    );
+    assert_eq!(code(&["scene/missing.scene", "-q"]), Some(1));
+    assert_eq!(code(&["no_such_scene", "-q"]), Some(1));
+    // an image format that does not exist is a mistake of the command line
+    assert_eq!(
+        code(&["scene/cornell_box.scene", "-o", "image.tiff"]),
+        Some(2)
+    );
+    assert_eq!(
+        code(&["scene/cornell_box.scene", "--format", "gif"]),
+        Some(2)
+    );
+}
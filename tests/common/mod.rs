@@ -0,0 +1,17 @@
+// the fixtures shared by the tests, every test file uses only some of them
+#![allow(dead_code)]
+
+use crate::material::{DiffuseLight, Lambertian};
+use crate::texture::SolidTexture;
+
+pub fn gray_texture() -> SolidTexture {
+    SolidTexture::new(0.5, 0.5, 0.5)
+}
+
+pub fn gray() -> Lambertian<SolidTexture> {
+    Lambertian::new(gray_texture())
+}
+
+pub fn light() -> DiffuseLight<SolidTexture> {
+    DiffuseLight::new(SolidTexture::new(1.0, 1.0, 1.0))
+}
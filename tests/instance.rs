@@ -0,0 +1,151 @@
+use raytracing_in_rust::*;
+
+mod common;
+
+use nalgebra::{Matrix4, Vector3};
+use std::sync::Arc;
+
+use crate::bvh::BVHNode;
+use crate::common::gray;
+use crate::cube::Cube;
+use crate::hittable::{HitRecord, Hittable, HittableList};
+use crate::instance::Instance;
+use crate::material::{BsdfSample, DiffuseLight, Lambertian, Material};
+use crate::ray::Ray;
+use crate::sampler::{IndependentSampler, Sampler};
+use crate::sphere::Sphere;
+use crate::texture::SolidTexture;
+use crate::transform::{rotation, scaling, translation, Transform};
+use crate::util::random_unit_vector;
+
+// a small tree: a trunk and a crown in a BVH
+fn tree() -> Arc<dyn Hittable> {
+    let trunk: Arc<dyn Hittable> = Arc::new(Cube::new(
+        Vector3::new(-0.1, 0.0, -0.1),
+        Vector3::new(0.1, 1.0, 0.1),
+        gray(),
+    ));
+    let crown: Arc<dyn Hittable> = Arc::new(Sphere::new(Vector3::new(0.0, 1.4, 0.0), 0.5, gray()));
+    Arc::new(BVHNode::from_list(vec![trunk, crown], 0.0, 1.0))
+}
+
+fn placement(i: usize) -> Matrix4<f64> {
+    let (x, z) = ((i % 100) as f64 * 2.0, (i / 100) as f64 * 2.0);
+    translation(&Vector3::new(x, 0.0, z))
+        * rotation(&Vector3::y(), i as f64 * 37.0)
+        * scaling(&Vector3::repeat(0.8 + (i % 5) as f64 * 0.1))
+}
+
+#[test]
+fn test_instances_share_the_prototype() {
+    let prototype = tree();
+    let forest: Vec<Arc<dyn Hittable>> = (0..10_000)
+        .map(|i| Arc::new(Instance::new(prototype.clone(), placement(i))) as Arc<dyn Hittable>)
+        .collect();
+    assert_eq!(Arc::strong_count(&prototype), 10_001);
+    let forest = BVHNode::from_list(forest, 0.0, 1.0);
+
+    // every instance is where its transform puts the prototype
+    let mut sampler = IndependentSampler::new(1);
+    for _ in 0..200 {
+        let i = (sampler.next_1d() * 10_000.0) as usize;
+        let tree = Transform::new(prototype.clone(), placement(i));
+        let target = placement(i).transform_point(&nalgebra::Point3::new(0.0, 1.0, 0.0));
+        let origin = target.coords + 3.0 * random_unit_vector(&mut sampler);
+        let ray = Ray::new(origin, target.coords - origin, 0.0);
+        let expected = tree.hit(&ray, 0.001, f64::MAX).unwrap();
+        let hit = forest.hit(&ray, 0.001, f64::MAX).unwrap();
+        assert!(hit.t <= expected.t + 1e-9);
+        if (hit.t - expected.t).abs() < 1e-9 {
+            assert!((hit.normal - expected.normal).norm() < 1e-9);
+        }
+    }
+}
+
+#[test]
+fn test_material_override() {
+    let prototype: Arc<dyn Hittable> = Arc::new(Sphere::new(Vector3::zeros(), 1.0, gray()));
+    let red: Arc<dyn Material> = Arc::new(Lambertian::new(SolidTexture::new(1.0, 0.0, 0.0)));
+    let plain = Instance::new(
+        prototype.clone(),
+        translation(&Vector3::new(-2.0, 0.0, 0.0)),
+    );
+    let painted = Instance::with_material(
+        prototype,
+        translation(&Vector3::new(2.0, 0.0, 0.0)),
+        red.clone(),
+    );
+    let mut sampler = IndependentSampler::new(2);
+    let mut reflected = |instance: &Instance, x: f64| {
+        let ray = Ray::new(Vector3::new(x, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0), 0.0);
+        let hit = instance.hit(&ray, 0.001, f64::MAX).unwrap();
+        assert!((hit.t - 4.0).abs() < 1e-9);
+        hit.material
+            .sample(&hit, &Vector3::new(0.0, 0.0, 1.0), &mut sampler)
+            .unwrap()
+            .f
+    };
+    let f = reflected(&plain, -2.0);
+    assert!(f.x > 0.0 && (f.x - f.y).abs() < 1e-9);
+    let f = reflected(&painted, 2.0);
+    assert!(f.x > 0.0 && f.y == 0.0 && f.z == 0.0);
+    assert_eq!(Arc::strong_count(&red), 2);
+
+    // an instance of a light is a light, unless it is painted over
+    let light: Arc<dyn Hittable> = Arc::new(Sphere::new(
+        Vector3::zeros(),
+        1.0,
+        DiffuseLight::new(SolidTexture::new(1.0, 1.0, 1.0)),
+    ));
+    let mut world = HittableList::default();
+    world.push(Instance::new(light.clone(), translation(&Vector3::y())));
+    world.push(Instance::with_material(
+        light,
+        translation(&Vector3::x()),
+        Arc::new(gray()),
+    ));
+    let mut lights = Vec::new();
+    world.collect_lights(&mut lights);
+    assert_eq!(lights.len(), 1);
+}
+
+// an emissive paint would be a light that is never sampled
+#[test]
+#[should_panic(expected = "cannot be emissive")]
+fn test_emissive_override_is_rejected() {
+    let prototype: Arc<dyn Hittable> = Arc::new(Sphere::new(Vector3::zeros(), 1.0, gray()));
+    Instance::with_material(
+        prototype,
+        Matrix4::identity(),
+        Arc::new(DiffuseLight::new(SolidTexture::new(4.0, 4.0, 4.0))),
+    );
+}
+
+// a material that cannot be cloned
+struct Unique(Lambertian<SolidTexture>);
+
+impl Material for Unique {
+    fn sample(
+        &self,
+        hit: &HitRecord,
+        wo: &Vector3<f64>,
+        sampler: &mut dyn Sampler,
+    ) -> Option<BsdfSample> {
+        self.0.sample(hit, wo, sampler)
+    }
+
+    fn emitted(&self, _u: f64, _v: f64, _p: &Vector3<f64>) -> Vector3<f64> {
+        Vector3::zeros()
+    }
+}
+
+#[test]
+fn test_cube_shares_its_material() {
+    let cube = Cube::new(Vector3::zeros(), Vector3::repeat(1.0), Unique(gray()));
+    let ray = Ray::new(
+        Vector3::new(0.5, 0.5, 3.0),
+        Vector3::new(0.0, 0.0, -1.0),
+        0.0,
+    );
+    assert!((cube.hit(&ray, 0.001, f64::MAX).unwrap().t - 2.0).abs() < 1e-9);
+}
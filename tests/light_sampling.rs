@@ -0,0 +1,156 @@
+use raytracing_in_rust::*;
+
+mod common;
+
+use nalgebra::Vector3;
+use std::f64::consts::PI;
+
+use crate::color::{color, PathDepth};
+use crate::common::light;
+use crate::disk::Disk;
+use crate::hittable::{Hittable, HittableList};
+use crate::material::Lambertian;
+use crate::plane::InfinitePlane;
+use crate::quad::Quad;
+use crate::ray::Ray;
+use crate::rect::{Plane, Rect};
+use crate::rotate::{Axis, Rotate};
+use crate::sampler::IndependentSampler;
+use crate::sphere::Sphere;
+use crate::texture::SolidTexture;
+use crate::traslate::Traslate;
+use crate::util::random_unit_vector;
+
+fn lights_of(world: &dyn Hittable) -> usize {
+    let mut lights = Vec::new();
+    world.collect_lights(&mut lights);
+    lights.len()
+}
+
+// the density of every light integrates to 1 over the sphere of directions,
+// and the directions it samples reach the light
+fn check_pdf(light: &dyn Hittable, origin: Vector3<f64>) {
+    let mut sampler = IndependentSampler::new(1);
+    let n = 200_000;
+    let integral: f64 = (0..n)
+        .map(|_| light.pdf_value(&origin, &random_unit_vector(&mut sampler)))
+        .sum::<f64>()
+        * 4.0
+        * PI
+        / n as f64;
+    assert!((integral - 1.0).abs() < 0.03, "{}", integral);
+    for _ in 0..100 {
+        let direction = light.random(&origin, &mut sampler);
+        assert!(light.pdf_value(&origin, &direction) > 0.0);
+    }
+}
+
+#[test]
+fn test_scenes_collect_their_lights() {
+    assert_eq!(lights_of(scenes::cornell_box().world.as_ref()), 1);
+    assert_eq!(lights_of(scenes::simple_light().world.as_ref()), 2);
+    assert_eq!(lights_of(scenes::two_spheres().world.as_ref()), 0);
+}
+
+#[test]
+fn test_light_pdfs_are_normalized() {
+    let origin = Vector3::new(0.5, 0.2, -0.3);
+    check_pdf(
+        &Sphere::new(Vector3::new(0.0, 3.0, 0.0), 1.5, light()),
+        origin,
+    );
+    check_pdf(
+        &Rect::new(Plane::ZX, -1.0, -2.0, 1.0, 0.5, 2.0, light()),
+        origin,
+    );
+    check_pdf(
+        &Quad::new(
+            Vector3::new(-1.0, 2.0, -1.0),
+            Vector3::new(2.0, 0.5, 0.0),
+            Vector3::new(0.0, 1.0, 1.5),
+            light(),
+        ),
+        origin,
+    );
+    check_pdf(
+        &Disk::new(
+            Vector3::new(1.0, 2.0, 0.0),
+            Vector3::new(1.0, -1.0, 0.5),
+            1.2,
+            light(),
+        ),
+        origin,
+    );
+    check_pdf(
+        &InfinitePlane::new(
+            Vector3::new(0.0, 3.0, 0.0),
+            Vector3::new(0.2, 1.0, 0.0),
+            light(),
+        ),
+        origin,
+    );
+    // the wrappers move the sampling along with the light
+    let moved = Traslate::new(
+        Rotate::new(
+            Axis::Y,
+            Rect::new(Plane::XY, -1.0, -1.0, 1.0, 1.0, 0.0, light()),
+            30.0,
+        ),
+        Vector3::new(0.0, 0.0, 2.0),
+    );
+    assert_eq!(lights_of(&moved), 1);
+    check_pdf(&moved, origin);
+}
+
+// a floor lit by a sphere light right above the shaded point: the irradiance
+// is pi * Le * (r / d)^2, the radiance leaving the floor albedo / 4
+#[test]
+fn test_light_sampling_is_unbiased_and_less_noisy() {
+    let mut world = HittableList::default();
+    world.push(Rect::new(
+        Plane::ZX,
+        -100.0,
+        -100.0,
+        100.0,
+        100.0,
+        0.0,
+        Lambertian::new(SolidTexture::new(0.5, 0.5, 0.5)),
+    ));
+    world.push(Sphere::new(Vector3::new(0.0, 2.0, 0.0), 1.0, light()));
+    let mut lights = Vec::new();
+    world.collect_lights(&mut lights);
+    assert_eq!(lights.len(), 1);
+
+    let ray = Ray::new(
+        Vector3::new(3.0, 1.0, 0.0),
+        Vector3::new(-3.0, -1.0, 0.0),
+        0.0,
+    );
+    let background = Vector3::zeros();
+    let n = 40_000;
+    let stats = |lights: &[&dyn Hittable]| {
+        let mut sampler = IndependentSampler::new(1);
+        let samples: Vec<f64> = (0..n)
+            .map(|_| {
+                color(
+                    &ray,
+                    &world,
+                    lights,
+                    &background,
+                    &PathDepth::new(1),
+                    &mut sampler,
+                )
+                .x
+            })
+            .collect();
+        let mean = samples.iter().sum::<f64>() / n as f64;
+        let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n as f64;
+        (mean, variance)
+    };
+
+    let (nee_mean, nee_variance) = stats(&lights);
+    let (bsdf_mean, bsdf_variance) = stats(&[]);
+    assert!((nee_mean - 0.125).abs() < 0.005, "{}", nee_mean);
+    assert!((bsdf_mean - 0.125).abs() < 0.005, "{}", bsdf_mean);
+    assert!(nee_variance < bsdf_variance / 2.0);
+}
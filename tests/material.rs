@@ -0,0 +1,118 @@
+use raytracing_in_rust::*;
+
+mod common;
+
+use nalgebra::Vector3;
+use std::f64::consts::PI;
+
+use crate::common::gray_texture;
+use crate::hittable::HitRecord;
+use crate::material::{Dielectric, DiffuseLight, Isotropic, Lambertian, Material, Metal};
+use crate::ray::Ray;
+use crate::sampler::IndependentSampler;
+use crate::util::random_unit_vector;
+
+fn hit_on<M: Material>(material: &M) -> HitRecord<'_> {
+    HitRecord {
+        t: 1.0,
+        u: 0.5,
+        v: 0.5,
+        p: Vector3::zeros(),
+        normal: Vector3::new(0.0, 1.0, 0.0),
+        material,
+        vertex_color: None,
+    }
+}
+
+// the samples agree with eval and pdf, and eval integrates to the albedo
+fn check_sampling<M: Material>(material: &M, albedo: f64) {
+    let hit = hit_on(material);
+    let wo = Vector3::new(1.0, 1.0, 0.0).normalize();
+    let mut sampler = IndependentSampler::new(1);
+    for _ in 0..100 {
+        let sample = material.sample(&hit, &wo, &mut sampler).unwrap();
+        assert!(!sample.delta);
+        assert!((sample.wi.norm() - 1.0).abs() < 1e-9);
+        assert!((sample.pdf - material.pdf(&hit, &wo, &sample.wi)).abs() < 1e-9);
+        assert!((sample.f - material.eval(&hit, &wo, &sample.wi)).norm() < 1e-9);
+        assert!((sample.f / sample.pdf - Vector3::repeat(albedo)).norm() < 1e-9);
+    }
+
+    let n = 200_000;
+    let (mut reflected, mut density) = (0.0, 0.0);
+    for _ in 0..n {
+        let wi = random_unit_vector(&mut sampler);
+        reflected += material.eval(&hit, &wo, &wi).x;
+        density += material.pdf(&hit, &wo, &wi);
+    }
+    let reflected = reflected * 4.0 * PI / n as f64;
+    let density = density * 4.0 * PI / n as f64;
+    assert!((reflected - albedo).abs() < 0.01, "{}", reflected);
+    assert!((density - 1.0).abs() < 0.02, "{}", density);
+}
+
+#[test]
+fn test_lambertian_is_cosine_weighted() {
+    let material = Lambertian::new(gray_texture());
+    check_sampling(&material, 0.5);
+
+    let hit = hit_on(&material);
+    let wo = Vector3::new(0.0, 1.0, 0.0);
+    let below = Vector3::new(0.0, -1.0, 0.0);
+    assert_eq!(material.pdf(&hit, &wo, &below), 0.0);
+    assert_eq!(material.pdf(&hit, &wo, &wo), 1.0 / PI);
+    let mut sampler = IndependentSampler::new(1);
+    for _ in 0..100 {
+        assert!(material.sample(&hit, &wo, &mut sampler).unwrap().wi.y >= 0.0);
+    }
+}
+
+#[test]
+fn test_isotropic_is_uniform() {
+    check_sampling(&Isotropic::new(gray_texture()), 0.5);
+}
+
+#[test]
+fn test_specular_lobes_are_deltas() {
+    let wo = Vector3::new(1.0, 1.0, 0.0).normalize();
+    let mut sampler = IndependentSampler::new(1);
+    let mirror = Metal::new(gray_texture(), 0.0);
+    let hit = hit_on(&mirror);
+    let sample = mirror.sample(&hit, &wo, &mut sampler).unwrap();
+    assert!(sample.delta);
+    assert!((sample.wi - Vector3::new(-1.0, 1.0, 0.0).normalize()).norm() < 1e-9);
+    assert_eq!(sample.f, Vector3::repeat(0.5));
+    assert_eq!(mirror.eval(&hit, &wo, &sample.wi), Vector3::zeros());
+    assert_eq!(mirror.pdf(&hit, &wo, &sample.wi), 0.0);
+
+    let glass = Dielectric::new(1.5);
+    let hit = hit_on(&glass);
+    for _ in 0..100 {
+        let sample = glass.sample(&hit, &wo, &mut sampler).unwrap();
+        assert!(sample.delta);
+        assert_eq!(sample.f / sample.pdf, Vector3::repeat(1.0));
+        // reflected above the surface, refracted below toward the normal
+        assert!(sample.wi.y > 0.0 || sample.wi.x.abs() < wo.x);
+        assert_eq!(glass.pdf(&hit, &wo, &sample.wi), 0.0);
+    }
+}
+
+#[test]
+fn test_scatter_follows_the_samples() {
+    let ray = Ray::new(
+        Vector3::new(-1.0, 1.0, 0.0),
+        Vector3::new(1.0, -1.0, 0.0),
+        0.5,
+    );
+    let lambertian = Lambertian::new(gray_texture());
+    let hit = hit_on(&lambertian);
+    let mut sampler = IndependentSampler::new(1);
+    let (scattered, attenuation) = lambertian.scatter(&ray, &hit, &mut sampler).unwrap();
+    assert_eq!(scattered.origin(), hit.p);
+    assert_eq!(scattered.time(), 0.5);
+    assert!((attenuation - Vector3::repeat(0.5)).norm() < 1e-9);
+
+    let light = DiffuseLight::new(gray_texture());
+    assert!(light.scatter(&ray, &hit_on(&light), &mut sampler).is_none());
+    assert!(light.is_emissive());
+}
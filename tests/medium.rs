@@ -0,0 +1,61 @@
+use raytracing_in_rust::*;
+
+mod common;
+
+use nalgebra::Vector3;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::common::{gray, gray_texture};
+use crate::cube::Cube;
+use crate::hittable::{Hittable, HittableList};
+use crate::medium::ConstantMedium;
+use crate::ray::Ray;
+use crate::texture::SolidTexture;
+
+// a slab of unit density between x0 and x1
+fn slab(x0: f64, x1: f64) -> ConstantMedium<Cube, SolidTexture> {
+    ConstantMedium::new(
+        Cube::new(
+            Vector3::new(x0, -1.0, -1.0),
+            Vector3::new(x1, 1.0, 1.0),
+            gray(),
+        ),
+        1.0,
+        gray_texture(),
+    )
+}
+
+// the fraction of parallel rays along x that cross the media
+fn transmittance(world: &HittableList) -> f64 {
+    let mut rng = StdRng::seed_from_u64(3);
+    let rays = 200_000;
+    let through = (0..rays)
+        .filter(|_| {
+            let origin = Vector3::new(-1.0, rng.gen_range(-0.5..0.5), rng.gen_range(-0.5..0.5));
+            let ray = Ray::new(origin, Vector3::new(1.0, 0.0, 0.0), 0.0);
+            world.hit(&ray, 0.001, f64::MAX).is_none()
+        })
+        .count();
+    through as f64 / rays as f64
+}
+
+// every medium draws its own distance, the transmittance of two media one
+// after the other is the product of the two
+#[test]
+fn test_media_in_series() {
+    let mut world = HittableList::default();
+    world.push(slab(0.0, 1.0));
+    let single = transmittance(&world);
+    assert!((single - (-1.0f64).exp()).abs() < 0.005, "{}", single);
+
+    world.push(slab(1.0, 2.0));
+    let series = transmittance(&world);
+    assert!((series - (-2.0f64).exp()).abs() < 0.005, "{}", series);
+
+    let mut world = HittableList::default();
+    world.push(slab(0.0, 0.5));
+    world.push(slab(2.0, 3.5));
+    let series = transmittance(&world);
+    assert!((series - (-2.0f64).exp()).abs() < 0.005, "{}", series);
+}
@@ -0,0 +1,119 @@
+use raytracing_in_rust::*;
+
+mod common;
+
+use nalgebra::{Vector2, Vector3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::common::gray;
+use crate::hittable::{Hittable, HittableList};
+use crate::mesh::TriangleMesh;
+use crate::ray::Ray;
+use crate::triangle::Triangle;
+
+// a bumpy square made of 2 * n * n triangles
+fn height_field(n: usize, rng: &mut StdRng) -> (Vec<Vector3<f64>>, Vec<[usize; 3]>) {
+    let mut vertices = Vec::new();
+    for j in 0..=n {
+        for i in 0..=n {
+            vertices.push(Vector3::new(i as f64, rng.gen_range(-0.5..0.5), j as f64));
+        }
+    }
+    let mut indices = Vec::new();
+    for j in 0..n {
+        for i in 0..n {
+            let a = j * (n + 1) + i;
+            let b = a + 1;
+            let c = a + n + 1;
+            let d = c + 1;
+            indices.push([a, c, b]);
+            indices.push([b, c, d]);
+        }
+    }
+    (vertices, indices)
+}
+
+#[test]
+fn test_triangle_barycentric_uv_and_normal() {
+    let triangle = Triangle::new(
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(1.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+        gray(),
+    );
+    let ray = Ray::new(
+        Vector3::new(0.25, 0.5, 2.0),
+        Vector3::new(0.0, 0.0, -1.0),
+        0.0,
+    );
+    let hit = triangle.hit(&ray, 0.001, f64::MAX).unwrap();
+    assert!((hit.t - 2.0).abs() < 1e-12);
+    assert!((hit.u - 0.25).abs() < 1e-12);
+    assert!((hit.v - 0.5).abs() < 1e-12);
+    assert!((hit.normal - Vector3::new(0.0, 0.0, 1.0)).norm() < 1e-12);
+
+    let miss = Ray::new(
+        Vector3::new(0.75, 0.5, 2.0),
+        Vector3::new(0.0, 0.0, -1.0),
+        0.0,
+    );
+    assert!(triangle.hit(&miss, 0.001, f64::MAX).is_none());
+
+    let triangle = triangle
+        .with_uvs([
+            Vector2::new(0.0, 0.0),
+            Vector2::new(2.0, 0.0),
+            Vector2::new(0.0, 4.0),
+        ])
+        .with_normals([
+            Vector3::new(0.0, 0.0, 1.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+        ]);
+    let hit = triangle.hit(&ray, 0.001, f64::MAX).unwrap();
+    assert!((hit.u - 0.5).abs() < 1e-12);
+    assert!((hit.v - 2.0).abs() < 1e-12);
+    let expected = Vector3::new(0.25, 0.0, 0.75).normalize();
+    assert!((hit.normal - expected).norm() < 1e-12);
+}
+
+#[test]
+fn test_mesh_matches_triangles() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let (vertices, indices) = height_field(20, &mut rng);
+    let mut list = HittableList::default();
+    for &[a, b, c] in indices.iter() {
+        list.push(Triangle::new(vertices[a], vertices[b], vertices[c], gray()));
+    }
+    let mesh = TriangleMesh::new(vertices, indices, gray());
+    assert_eq!(mesh.triangle_count(), 800);
+    assert_eq!(mesh.vertex_count(), 441);
+
+    let bbox = mesh.bounding_box(0.0, 1.0).unwrap();
+    assert!(bbox.min.x <= 0.0 && bbox.max.x >= 20.0);
+    assert!(bbox.min.z <= 0.0 && bbox.max.z >= 20.0);
+
+    let mut hits = 0;
+    for _ in 0..2000 {
+        let origin = Vector3::new(
+            rng.gen_range(-5.0..25.0),
+            rng.gen_range(1.0..10.0),
+            rng.gen_range(-5.0..25.0),
+        );
+        let target = Vector3::new(rng.gen_range(0.0..20.0), 0.0, rng.gen_range(0.0..20.0));
+        let ray = Ray::new(origin, target - origin, 0.0);
+        let expected = list.hit(&ray, 0.001, f64::MAX);
+        let found = mesh.hit(&ray, 0.001, f64::MAX);
+        match (expected, found) {
+            (Some(e), Some(f)) => {
+                hits += 1;
+                assert!((e.t - f.t).abs() < 1e-9);
+                assert!((e.normal - f.normal).norm() < 1e-9);
+            }
+            (None, None) => {}
+            _ => panic!("mesh and triangle list disagree"),
+        }
+    }
+    assert!(hits > 1000);
+}
@@ -0,0 +1,118 @@
+use raytracing_in_rust::*;
+
+use nalgebra::Vector3;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::hittable::Hittable;
+use crate::ray::Ray;
+
+fn write_files(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("raytracing_obj_{}", name));
+    fs::create_dir_all(&directory).unwrap();
+    for (file, content) in files {
+        fs::write(directory.join(file), content).unwrap();
+    }
+    directory
+}
+
+const CUBE_MTL: &str = "
+newmtl white
+Kd 0.8 0.8 0.8
+
+newmtl lamp
+Kd 0 0 0
+Ke 10 10 10
+";
+
+// unit cube, the top face is a light. the faces use quads, negative indices
+// and every form of vertex reference
+const CUBE_OBJ: &str = "
+mtllib cube.mtl
+o cube
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 0 0 1
+v 1 0 1
+v 1 1 1
+v 0 1 1
+vt 0 0
+vt 1 0
+vt 1 1
+vn 0 0 -1
+usemtl white
+f 1/1/1 4/3/1 3/2/1 2/1/1
+f 5 6 7 8
+f 1//1 2//1 6//1 5//1
+f -7 -4 -3 -6
+f 1/1 5/2 8/3 4/1
+usemtl lamp
+g top
+f 4 8 7 3
+";
+
+#[test]
+fn test_load_cube() {
+    let directory = write_files("cube", &[("cube.obj", CUBE_OBJ), ("cube.mtl", CUBE_MTL)]);
+    let model = obj::load(directory.join("cube.obj")).unwrap();
+
+    let bbox = model.bounding_box(0.0, 1.0).unwrap();
+    assert!((bbox.min - Vector3::new(0.0, 0.0, 0.0)).norm() < 1e-3);
+    assert!((bbox.max - Vector3::new(1.0, 1.0, 1.0)).norm() < 1e-3);
+
+    // from above the light is hit
+    let down = Ray::new(
+        Vector3::new(0.5, 3.0, 0.5),
+        Vector3::new(0.0, -1.0, 0.0),
+        0.0,
+    );
+    let hit = model.hit(&down, 0.001, f64::MAX).unwrap();
+    assert!((hit.t - 2.0).abs() < 1e-9);
+    let emitted = hit.material.emitted(hit.u, hit.v, &hit.p);
+    assert!((emitted - Vector3::new(10.0, 10.0, 10.0)).norm() < 1e-12);
+
+    // from the front a white face, which has uvs and normals
+    let forward = Ray::new(
+        Vector3::new(0.5, 0.5, -2.0),
+        Vector3::new(0.0, 0.0, 1.0),
+        0.0,
+    );
+    let hit = model.hit(&forward, 0.001, f64::MAX).unwrap();
+    assert!((hit.t - 2.0).abs() < 1e-9);
+    assert!((hit.normal - Vector3::new(0.0, 0.0, -1.0)).norm() < 1e-9);
+    assert!(hit.material.emitted(hit.u, hit.v, &hit.p).norm() == 0.0);
+}
+
+#[test]
+fn test_errors_have_line_numbers() {
+    let directory = write_files(
+        "errors",
+        &[
+            ("number.obj", "v 0 0 0\nv 1 0 0\n\nv 1 x 0\nf 1 2 3\n"),
+            ("index.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n"),
+            ("face.obj", "v 0 0 0\nv 1 0 0\nf 1 2\n"),
+            (
+                "material.obj",
+                "v 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl nope\nf 1 2 3\n",
+            ),
+            ("empty.obj", "# nothing here\n"),
+        ],
+    );
+
+    let load_error = |file: &str| obj::load(directory.join(file)).err().unwrap();
+
+    let error = load_error("number.obj");
+    assert_eq!(error.line, 4);
+    assert!(error.to_string().contains("number.obj:4:"));
+
+    let error = load_error("index.obj");
+    assert_eq!(error.line, 4);
+    assert!(error.message.contains("out of range"));
+
+    assert_eq!(load_error("face.obj").line, 3);
+    assert_eq!(load_error("material.obj").line, 5);
+    assert_eq!(load_error("empty.obj").line, 0);
+    assert_eq!(load_error("missing.obj").line, 0);
+}
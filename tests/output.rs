@@ -0,0 +1,82 @@
+use raytracing_in_rust::*;
+
+use nalgebra::Vector3;
+
+use crate::output::OutputFormat;
+use crate::renderer::FrameBuffer;
+use crate::tonemap::{ToneMapOperator, ToneMapping};
+
+// a 3x2 frame with a light much brighter than 1 and a dark pixel
+fn frame() -> FrameBuffer {
+    let mut frame = FrameBuffer::new(3, 2);
+    frame.set(0, 0, Vector3::new(15.0, 7.5, 0.25));
+    frame.set(1, 0, Vector3::new(0.25, 0.25, 0.25));
+    frame.set(2, 1, Vector3::new(1.0, 0.0, 0.5));
+    frame
+}
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("raytracing_output_{}", name))
+}
+
+#[test]
+fn test_formats_from_names_and_paths() {
+    assert_eq!(OutputFormat::from_name("PNG16"), Some(OutputFormat::Png16));
+    assert_eq!(OutputFormat::from_path("a/b.exr"), Some(OutputFormat::Exr));
+    assert_eq!(
+        OutputFormat::from_path("image.hdr"),
+        Some(OutputFormat::Hdr)
+    );
+    assert_eq!(OutputFormat::from_path("image.png16"), None);
+    assert_eq!(OutputFormat::from_path("image"), None);
+    assert!(OutputFormat::Exr.is_float() && !OutputFormat::Png.is_float());
+}
+
+#[test]
+fn test_float_formats_keep_the_radiance() {
+    let frame = frame();
+    for &(name, tolerance) in [("frame.exr", 1e-6), ("frame.hdr", 0.02)].iter() {
+        let path = temp_path(name);
+        let format = OutputFormat::from_path(&path).unwrap();
+        // the float formats are never tone mapped
+        let tone_mapping = ToneMapping {
+            operator: ToneMapOperator::Aces,
+            ..Default::default()
+        };
+        output::save(&frame, &path, format, &tone_mapping).unwrap();
+        let image = image::open(&path).unwrap().to_rgb32f();
+        assert_eq!(image.dimensions(), (3, 2));
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let expected = frame.get(x as usize, y as usize);
+            for c in 0..3 {
+                let value = pixel.0[c] as f64;
+                assert!(
+                    (value - expected[c]).abs() <= tolerance * expected[c].max(1.0),
+                    "{} {} {} {}",
+                    name,
+                    x,
+                    y,
+                    value
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_png_is_tone_mapped() {
+    let frame = frame();
+    let clamp = ToneMapping::default();
+    let path = temp_path("frame.png");
+    output::save(&frame, &path, OutputFormat::Png, &clamp).unwrap();
+    let image = image::open(&path).unwrap().to_rgb8();
+    assert_eq!(image.get_pixel(0, 0).0, [255, 255, 137]);
+    assert_eq!(image.get_pixel(1, 0).0, [137, 137, 137]);
+    assert_eq!(image.get_pixel(0, 1).0, [0, 0, 0]);
+
+    let path = temp_path("frame16.png");
+    output::save(&frame, &path, OutputFormat::Png16, &clamp).unwrap();
+    let image = image::open(&path).unwrap();
+    assert_eq!(image.color(), image::ColorType::Rgb16);
+    assert_eq!(image.to_rgb16().get_pixel(1, 0).0, [35199, 35199, 35199]);
+}
@@ -0,0 +1,171 @@
+use raytracing_in_rust::*;
+
+use nalgebra::Vector3;
+
+use crate::color::{color, PathDepth};
+use crate::hittable::{FlipNormals, HitRecord, Hittable, HittableList};
+use crate::material::{BsdfSample, Lambertian, Material, Metal};
+use crate::ray::Ray;
+use crate::rect::{Plane, Rect};
+use crate::sampler::{IndependentSampler, Sampler};
+use crate::sphere::Sphere;
+use crate::texture::SolidTexture;
+
+// a diffuse surface that also glows, inside a closed sphere of it every
+// bounce adds the emission times albedo^bounce
+struct Glow {
+    surface: Lambertian<SolidTexture>,
+}
+
+impl Material for Glow {
+    fn sample(
+        &self,
+        hit: &HitRecord,
+        wo: &Vector3<f64>,
+        sampler: &mut dyn Sampler,
+    ) -> Option<BsdfSample> {
+        self.surface.sample(hit, wo, sampler)
+    }
+
+    fn emitted(&self, _u: f64, _v: f64, _p: &Vector3<f64>) -> Vector3<f64> {
+        Vector3::new(1.0, 1.0, 1.0)
+    }
+}
+
+fn furnace() -> FlipNormals<Sphere<Glow>> {
+    FlipNormals::new(Sphere::new(
+        Vector3::zeros(),
+        1.0,
+        Glow {
+            surface: Lambertian::new(SolidTexture::new(0.5, 0.5, 0.5)),
+        },
+    ))
+}
+
+fn trace(world: &dyn Hittable, depth: &PathDepth, sampler: &mut dyn Sampler) -> f64 {
+    let ray = Ray::new(Vector3::zeros(), Vector3::new(0.3, 0.4, -0.5), 0.0);
+    color(&ray, world, &[], &Vector3::zeros(), depth, sampler).x
+}
+
+#[test]
+fn test_depth_limits() {
+    let world = furnace();
+    let trace = |depth: &PathDepth| trace(&world, depth, &mut IndependentSampler::new(1));
+    // without roulette the sum of the series is exact
+    let expected = |bounces: i32| 2.0 * (1.0 - 0.5f64.powi(bounces + 1));
+    assert!((trace(&PathDepth::new(0)) - 1.0).abs() < 1e-12);
+    assert!((trace(&PathDepth::new(4)) - expected(4)).abs() < 1e-12);
+
+    let diffuse = PathDepth {
+        diffuse: 2,
+        ..PathDepth::new(10)
+    };
+    assert!((trace(&diffuse) - expected(2)).abs() < 1e-12);
+    // other lobes do not count against the diffuse limit
+    let specular = PathDepth {
+        specular: 0,
+        ..PathDepth::new(3)
+    };
+    assert!((trace(&specular) - expected(3)).abs() < 1e-12);
+}
+
+#[test]
+fn test_russian_roulette_is_unbiased() {
+    let world = furnace();
+    let depth = PathDepth {
+        min: 1,
+        ..PathDepth::new(1000)
+    };
+    let mut sampler = IndependentSampler::new(1);
+    let n = 100_000;
+    let samples: Vec<f64> = (0..n)
+        .map(|_| trace(&world, &depth, &mut sampler))
+        .collect();
+    let mean = samples.iter().sum::<f64>() / n as f64;
+    assert!((mean - 2.0).abs() < 0.02, "{}", mean);
+    // the paths that survive carry more weight
+    assert!(samples.iter().any(|&s| s > 2.0));
+    assert!(samples.iter().any(|&s| s < 2.0));
+}
+
+// two facing mirrors keep the throughput at 1, a ray that is almost
+// orthogonal to them escapes to the background after about 100000 bounces,
+// only max stops it before. a long path does not grow the stack
+#[test]
+fn test_long_paths_between_mirrors() {
+    let mirror = || Metal::new(SolidTexture::new(1.0, 1.0, 1.0), 0.0);
+    let mut world = HittableList::default();
+    world.push(Rect::new(
+        Plane::XY,
+        -10.0,
+        -10.0,
+        10.0,
+        10.0,
+        0.0,
+        mirror(),
+    ));
+    world.push(FlipNormals::new(Rect::new(
+        Plane::XY,
+        -10.0,
+        -10.0,
+        10.0,
+        10.0,
+        1.0,
+        mirror(),
+    )));
+    let ray = Ray::new(
+        Vector3::new(0.0, 0.0, 0.5),
+        Vector3::new(1e-4, 0.0, 1.0),
+        0.0,
+    );
+    let background = Vector3::new(1.0, 1.0, 1.0);
+    let depth = PathDepth {
+        min: 0,
+        ..PathDepth::new(200_000)
+    };
+    assert_eq!(
+        color(
+            &ray,
+            &world,
+            &[],
+            &background,
+            &depth,
+            &mut IndependentSampler::new(1)
+        )
+        .x,
+        1.0
+    );
+
+    let short = PathDepth {
+        max: 50_000,
+        ..depth
+    };
+    assert_eq!(
+        color(
+            &ray,
+            &world,
+            &[],
+            &background,
+            &short,
+            &mut IndependentSampler::new(1)
+        )
+        .x,
+        0.0
+    );
+    let specular = PathDepth {
+        specular: 5,
+        ..depth
+    };
+    assert_eq!(
+        color(
+            &ray,
+            &world,
+            &[],
+            &background,
+            &specular,
+            &mut IndependentSampler::new(1)
+        )
+        .x,
+        0.0
+    );
+}
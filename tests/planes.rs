@@ -0,0 +1,197 @@
+use raytracing_in_rust::*;
+
+mod common;
+
+use nalgebra::Vector3;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
+
+use crate::bvh::BVHNode;
+use crate::common::{gray, light};
+use crate::disk::Disk;
+use crate::hittable::{Hittable, HittableList};
+use crate::plane::InfinitePlane;
+use crate::quad::Quad;
+use crate::ray::Ray;
+use crate::rect::{Plane, Rect};
+use crate::sampler::IndependentSampler;
+use crate::sphere::Sphere;
+
+#[test]
+fn test_quad_hit_and_uv() {
+    // a tilted quad whose edges are not orthogonal
+    let quad = Quad::new(
+        Vector3::new(1.0, 0.0, 0.0),
+        Vector3::new(2.0, 0.0, 0.0),
+        Vector3::new(1.0, 1.0, 1.0),
+        gray(),
+    );
+    let target = Vector3::new(1.0, 0.0, 0.0)
+        + 0.25 * Vector3::new(2.0, 0.0, 0.0)
+        + 0.5 * Vector3::new(1.0, 1.0, 1.0);
+    let origin = Vector3::new(0.0, 5.0, -3.0);
+    let hit = quad
+        .hit(&Ray::new(origin, target - origin, 0.0), 0.001, f64::MAX)
+        .unwrap();
+    assert!((hit.t - 1.0).abs() < 1e-9);
+    assert!((hit.u - 0.25).abs() < 1e-9 && (hit.v - 0.5).abs() < 1e-9);
+    assert!((hit.normal - Vector3::new(0.0, -1.0, 1.0).normalize()).norm() < 1e-9);
+    assert!((quad.area() - 8.0f64.sqrt()).abs() < 1e-9);
+
+    // past the far edge
+    let outside = target + 0.6 * Vector3::new(1.0, 1.0, 1.0);
+    assert!(quad
+        .hit(&Ray::new(origin, outside - origin, 0.0), 0.001, f64::MAX)
+        .is_none());
+
+    let bbox = quad.bounding_box(0.0, 1.0).unwrap();
+    assert_eq!(bbox.min, Vector3::new(1.0, 0.0, 0.0));
+    assert_eq!(bbox.max, Vector3::new(4.0, 1.0, 1.0));
+}
+
+// the same rect as a quad is sampled with the same density
+#[test]
+fn test_quad_matches_rect() {
+    let rect = Rect::new(Plane::ZX, -1.0, -2.0, 1.0, 0.5, 2.0, light());
+    let quad = Quad::new(
+        Vector3::new(-2.0, 2.0, -1.0),
+        Vector3::new(0.0, 0.0, 2.0),
+        Vector3::new(2.5, 0.0, 0.0),
+        light(),
+    );
+    let origin = Vector3::new(0.3, -1.0, 0.2);
+    let mut sampler = IndependentSampler::new(3);
+    for _ in 0..100 {
+        let direction = quad.random(&origin, &mut sampler);
+        let ray = Ray::new(origin, direction, 0.0);
+        let (a, b) = (
+            quad.hit(&ray, 0.001, f64::MAX).unwrap(),
+            rect.hit(&ray, 0.001, f64::MAX).unwrap(),
+        );
+        assert!((a.t - b.t).abs() < 1e-9);
+        assert!((a.u - b.u).abs() < 1e-9 && (a.v - b.v).abs() < 1e-9);
+        let (a, b) = (
+            quad.pdf_value(&origin, &direction),
+            rect.pdf_value(&origin, &direction),
+        );
+        assert!((a - b).abs() < 1e-9 * b, "{} {}", a, b);
+    }
+}
+
+#[test]
+fn test_disk_uv_and_bounding_box() {
+    let center = Vector3::new(1.0, 2.0, 3.0);
+    let disk = Disk::new(center, Vector3::new(1.0, 2.0, -0.5), 1.5, gray());
+    let bbox = disk.bounding_box(0.0, 1.0).unwrap();
+    let mut sampler = IndependentSampler::new(5);
+    let origin = Vector3::new(-2.0, 6.0, 1.0);
+    let mut border = Vector3::zeros();
+    for _ in 0..1000 {
+        let direction = disk.random(&origin, &mut sampler);
+        let hit = disk
+            .hit(&Ray::new(origin, direction, 0.0), 0.001, f64::MAX)
+            .unwrap();
+        assert!((hit.p - origin - direction).norm() < 1e-9);
+        assert!((0.0..1.0).contains(&hit.u) && (0.0..=1.0).contains(&hit.v));
+        assert!((hit.v * 1.5 - (hit.p - center).norm()).abs() < 1e-9);
+        for a in 0..3 {
+            assert!(hit.p[a] >= bbox.min[a] && hit.p[a] <= bbox.max[a]);
+        }
+        border = border.sup(&(hit.p - center).abs());
+    }
+    // the box is tight
+    for a in 0..3 {
+        assert!(bbox.max[a] - center[a] - border[a] < 0.05);
+    }
+
+    let flat = Disk::new(Vector3::zeros(), Vector3::new(0.0, 1.0, 0.0), 2.0, gray());
+    let bbox = flat.bounding_box(0.0, 1.0).unwrap();
+    assert!((bbox.max.x - 2.0).abs() < 1e-3 && (bbox.max.z - 2.0).abs() < 1e-3);
+    assert!(bbox.max.y < 1e-3);
+}
+
+#[test]
+fn test_infinite_plane_uv() {
+    let plane = InfinitePlane::new(Vector3::zeros(), Vector3::new(0.0, 0.0, 1.0), gray());
+    assert!(plane.bounding_box(0.0, 1.0).is_none());
+    let mut rng = StdRng::seed_from_u64(1);
+    for _ in 0..100 {
+        let target = Vector3::new(rng.gen_range(-1e3..1e3), rng.gen_range(-1e3..1e3), 0.0);
+        let origin = Vector3::new(0.0, 0.0, 1.0);
+        let hit = plane
+            .hit(&Ray::new(origin, target - origin, 0.0), 0.001, f64::MAX)
+            .unwrap();
+        assert!((hit.t - 1.0).abs() < 1e-9);
+        assert!((0.0..1.0).contains(&hit.u) && (0.0..1.0).contains(&hit.v));
+    }
+    // parallel to the plane
+    let ray = Ray::new(
+        Vector3::new(0.0, 0.0, 1.0),
+        Vector3::new(1.0, 0.0, 0.0),
+        0.0,
+    );
+    assert!(plane.hit(&ray, 0.001, f64::MAX).is_none());
+}
+
+#[test]
+fn test_bvh_keeps_unbounded_primitives_apart() {
+    let mut rng = StdRng::seed_from_u64(9);
+    let mut objects: Vec<Arc<dyn Hittable>> = vec![
+        Arc::new(InfinitePlane::new(
+            Vector3::new(0.0, -5.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            gray(),
+        )),
+        Arc::new(InfinitePlane::new(
+            Vector3::new(0.0, 0.0, 20.0),
+            Vector3::new(0.3, 0.0, -1.0),
+            light(),
+        )),
+    ];
+    for _ in 0..50 {
+        let center = Vector3::new(
+            rng.gen_range(-10.0..10.0),
+            rng.gen_range(-5.0..10.0),
+            rng.gen_range(-10.0..10.0),
+        );
+        objects.push(Arc::new(Sphere::new(center, 0.5, gray())));
+        objects.push(Arc::new(Quad::new(
+            center + Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(rng.gen_range(0.1..1.0), 0.0, rng.gen_range(0.1..1.0)),
+            Vector3::new(0.0, rng.gen_range(0.1..1.0), 0.0),
+            gray(),
+        )));
+        objects.push(Arc::new(Disk::new(
+            center - Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(rng.gen_range(-1.0..1.0), 1.0, 0.0),
+            0.5,
+            gray(),
+        )));
+    }
+    let mut list = HittableList::default();
+    for object in objects.iter() {
+        list.push(object.clone());
+    }
+    let bvh = BVHNode::from_list(objects, 0.0, 1.0);
+    assert!(bvh.bounding_box(0.0, 1.0).is_none());
+    let mut lights = Vec::new();
+    bvh.collect_lights(&mut lights);
+    assert_eq!(lights.len(), 1);
+
+    for _ in 0..2000 {
+        let origin = Vector3::new(
+            rng.gen_range(-15.0..15.0),
+            rng.gen_range(-4.0..15.0),
+            rng.gen_range(-15.0..15.0),
+        );
+        let direction = Vector3::new(
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+        );
+        let ray = Ray::new(origin, direction, 0.0);
+        let expected = list.hit(&ray, 0.001, f64::MAX).map(|hit| hit.t);
+        assert_eq!(expected, bvh.hit(&ray, 0.001, f64::MAX).map(|hit| hit.t));
+    }
+}
@@ -0,0 +1,204 @@
+use raytracing_in_rust::*;
+
+use nalgebra::Vector3;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::hittable::Hittable;
+use crate::material::Lambertian;
+use crate::ray::Ray;
+use crate::sampler::IndependentSampler;
+use crate::texture::{SolidTexture, VertexColorTexture};
+
+fn write_file(name: &str, content: &[u8]) -> PathBuf {
+    let directory = std::env::temp_dir().join("raytracing_ply");
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join(name);
+    fs::write(&path, content).unwrap();
+    path
+}
+
+fn vertex_color_material() -> Lambertian<VertexColorTexture<SolidTexture>> {
+    Lambertian::new(VertexColorTexture::new(SolidTexture::new(0.5, 0.5, 0.5)))
+}
+
+// unit square on the xy plane made of a single quad, red on the left
+// and blue on the right
+const SQUARE_PLY: &str = "ply
+format ascii 1.0
+comment a colored square
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 0 0 255
+1 1 0 0 0 255
+0 1 0 255 0 0
+4 0 1 2 3
+";
+
+fn binary_square(big_endian: bool) -> Vec<u8> {
+    let format = if big_endian {
+        "binary_big_endian"
+    } else {
+        "binary_little_endian"
+    };
+    let mut data = format!(
+        "ply\nformat {} 1.0\nelement vertex 4\nproperty float x\nproperty float y\n\
+         property float z\nproperty float nx\nproperty float ny\nproperty float nz\n\
+         element face 2\nproperty list uchar uint vertex_indices\nend_header\n",
+        format
+    )
+    .into_bytes();
+    let vertices = [
+        [0.0f32, 0.0, 0.0],
+        [1.0, 0.0, 0.0],
+        [1.0, 1.0, 0.0],
+        [0.0, 1.0, 0.0],
+    ];
+    for vertex in vertices.iter() {
+        for value in vertex.iter().chain([0.0f32, 0.0, 1.0].iter()) {
+            if big_endian {
+                data.extend_from_slice(&value.to_be_bytes());
+            } else {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+    for face in [[0u32, 1, 2], [0, 2, 3]].iter() {
+        data.push(3);
+        for index in face.iter() {
+            if big_endian {
+                data.extend_from_slice(&index.to_be_bytes());
+            } else {
+                data.extend_from_slice(&index.to_le_bytes());
+            }
+        }
+    }
+    data
+}
+
+fn ray_at(x: f64, y: f64) -> Ray {
+    Ray::new(Vector3::new(x, y, 1.0), Vector3::new(0.0, 0.0, -1.0), 0.0)
+}
+
+#[test]
+fn test_load_ascii_with_colors() {
+    let path = write_file("square.ply", SQUARE_PLY.as_bytes());
+    let mesh = ply::load(&path, vertex_color_material()).unwrap();
+    assert_eq!(mesh.vertex_count(), 4);
+    assert_eq!(mesh.triangle_count(), 2);
+
+    let hit = mesh.hit(&ray_at(0.1, 0.5), 0.001, f64::MAX).unwrap();
+    assert!((hit.t - 1.0).abs() < 1e-9);
+    let color = hit.vertex_color.unwrap();
+    assert!((color - Vector3::new(0.9, 0.0, 0.1)).norm() < 1e-9);
+
+    // the color reaches the material as albedo
+    let (_, attenuation) = hit
+        .material
+        .scatter(&ray_at(0.1, 0.5), &hit, &mut IndependentSampler::new(1))
+        .unwrap();
+    assert!((attenuation - color).norm() < 1e-12);
+
+    assert!(mesh.hit(&ray_at(1.5, 0.5), 0.001, f64::MAX).is_none());
+}
+
+#[test]
+fn test_load_binary() {
+    for &big_endian in [false, true].iter() {
+        let path = write_file(
+            &format!("square_{}.ply", big_endian),
+            &binary_square(big_endian),
+        );
+        let mesh = ply::load(&path, vertex_color_material()).unwrap();
+        assert_eq!(mesh.triangle_count(), 2);
+
+        let hit = mesh.hit(&ray_at(0.7, 0.2), 0.001, f64::MAX).unwrap();
+        assert!((hit.t - 1.0).abs() < 1e-6);
+        assert!((hit.normal - Vector3::new(0.0, 0.0, 1.0)).norm() < 1e-6);
+        // no colors in the file, the fallback is used
+        assert!(hit.vertex_color.is_none());
+        let (_, attenuation) = hit
+            .material
+            .scatter(&ray_at(0.7, 0.2), &hit, &mut IndependentSampler::new(1))
+            .unwrap();
+        assert!((attenuation - Vector3::new(0.5, 0.5, 0.5)).norm() < 1e-12);
+    }
+}
+
+// the elements can be in any order, the faces here come before the vertices
+#[test]
+fn test_faces_before_vertices() {
+    let text = "ply
+format ascii 1.0
+element face 1
+property list uchar int vertex_indices
+element vertex 3
+property float x
+property float y
+property float z
+end_header
+3 0 1 2
+0 0 0
+1 0 0
+0 1 0
+";
+    let mesh = ply::load(
+        write_file("faces_first.ply", text.as_bytes()),
+        vertex_color_material(),
+    )
+    .unwrap();
+    let hit = mesh.hit(&ray_at(0.25, 0.25), 0.001, f64::MAX).unwrap();
+    assert!((hit.t - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_errors() {
+    let load_error = |name: &str, content: &[u8]| {
+        ply::load(write_file(name, content), vertex_color_material())
+            .err()
+            .unwrap()
+    };
+
+    let error = load_error("magic.ply", b"obj\nformat ascii 1.0\nend_header\n");
+    assert_eq!(error.line, 1);
+
+    let error = load_error(
+        "type.ply",
+        b"ply\nformat ascii 1.0\nelement vertex 1\nproperty quad x\nend_header\n",
+    );
+    assert_eq!(error.line, 4);
+    assert!(error.to_string().contains("type.ply:4:"));
+
+    let text = SQUARE_PLY.replace("1 1 0 0 0 255", "1 1 0 0 zero 255");
+    assert_eq!(load_error("value.ply", text.as_bytes()).line, 16);
+
+    let text = SQUARE_PLY.replace("4 0 1 2 3", "4 0 1 2 7");
+    assert!(load_error("index.ply", text.as_bytes())
+        .message
+        .contains("missing vertex"));
+
+    // indices must be whole and not negative, not truncated or clamped to 0
+    let text = SQUARE_PLY.replace("4 0 1 2 3", "4 0 1.7 2 3");
+    let error = load_error("fraction.ply", text.as_bytes());
+    assert_eq!(error.line, 18);
+    assert!(error.message.contains("'1.7' is not a vertex index"));
+    let text = SQUARE_PLY.replace("4 0 1 2 3", "4 -1 1 2 3");
+    assert!(load_error("negative.ply", text.as_bytes())
+        .message
+        .contains("'-1' is not a vertex index"));
+
+    let mut data = binary_square(false);
+    data.truncate(data.len() - 3);
+    let error = load_error("truncated.ply", &data);
+    assert_eq!(error.line, 0);
+    assert!(error.message.contains("face 1"));
+}
@@ -0,0 +1,231 @@
+use raytracing_in_rust::*;
+
+mod common;
+
+use nalgebra::{Vector2, Vector3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::capsule::Capsule;
+use crate::common::gray;
+use crate::hittable::Hittable;
+use crate::quadric::{Cone, Cylinder};
+use crate::ray::Ray;
+use crate::torus::Torus;
+
+// the signed distance to a polygon (Quilez), for the profile of a solid of
+// revolution around the y axis
+fn polygon_distance(vertices: &[Vector2<f64>], p: Vector2<f64>) -> f64 {
+    let mut d = (p - vertices[0]).norm_squared();
+    let mut sign = 1.0;
+    for i in 0..vertices.len() {
+        let j = (i + vertices.len() - 1) % vertices.len();
+        let e = vertices[j] - vertices[i];
+        let w = p - vertices[i];
+        let b = w - e * (w.dot(&e) / e.dot(&e)).clamp(0.0, 1.0);
+        d = d.min(b.norm_squared());
+        let c = [
+            p.y >= vertices[i].y,
+            p.y < vertices[j].y,
+            e.x * w.y > e.y * w.x,
+        ];
+        if c.iter().all(|&c| c) || c.iter().all(|&c| !c) {
+            sign = -sign;
+        }
+    }
+    sign * d.sqrt()
+}
+
+fn revolution(profile: Vec<Vector2<f64>>) -> impl Fn(&Vector3<f64>) -> f64 {
+    move |p| polygon_distance(&profile, Vector2::new(p.x.hypot(p.z), p.y))
+}
+
+// the first hit of a sphere tracer, the reference of the analytic hits
+fn march(distance: &dyn Fn(&Vector3<f64>) -> f64, ray: &Ray) -> Option<f64> {
+    let direction = ray.direction().normalize();
+    let mut t = 0.0;
+    for _ in 0..100_000 {
+        let d = distance(&(ray.origin() + t * direction));
+        if d < 1e-8 {
+            return Some(t / ray.direction().norm());
+        }
+        t += d;
+        if t > 100.0 {
+            return None;
+        }
+    }
+    None
+}
+
+fn gradient(distance: &dyn Fn(&Vector3<f64>) -> f64, p: &Vector3<f64>) -> Vector3<f64> {
+    let e = 1e-6;
+    Vector3::from_fn(|i, _| {
+        let mut offset = Vector3::zeros();
+        offset[i] = e;
+        distance(&(p + offset)) - distance(&(p - offset))
+    })
+    .normalize()
+}
+
+// random rays from outside toward the box of the shape: the analytic hits
+// are where the sphere tracer stops, with the normal of the distance field
+fn check_against_marching(shape: &dyn Hittable, distance: &dyn Fn(&Vector3<f64>) -> f64) {
+    let bbox = shape.bounding_box(0.0, 1.0).unwrap();
+    let center = bbox.centroid();
+    let mut rng = StdRng::seed_from_u64(17);
+    let mut hits = 0;
+    for _ in 0..1000 {
+        let origin = center
+            + Vector3::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            )
+            .normalize()
+                * 8.0;
+        let target = Vector3::from_fn(|i, _| rng.gen_range(bbox.min[i] - 0.2..bbox.max[i] + 0.2));
+        // the directions are not normalized
+        let ray = Ray::new(origin, (target - origin) * 0.5, 0.0);
+        let expected = march(distance, &ray);
+        let hit = shape.hit(&ray, 0.001, f64::MAX);
+        assert_eq!(expected.is_some(), hit.is_some(), "{:?}", ray);
+        if let (Some(t), Some(hit)) = (expected, hit) {
+            hits += 1;
+            assert!((t - hit.t).abs() < 1e-5, "{} {}", t, hit.t);
+            assert!(hit.normal.dot(&gradient(distance, &hit.p)) > 0.999);
+            assert!((hit.normal.norm() - 1.0).abs() < 1e-9);
+            assert!((0.0..=1.0).contains(&hit.u) && (0.0..=1.0).contains(&hit.v));
+            for i in 0..3 {
+                assert!(hit.p[i] >= bbox.min[i] - 1e-9 && hit.p[i] <= bbox.max[i] + 1e-9);
+            }
+        }
+    }
+    assert!(hits > 300, "{}", hits);
+}
+
+#[test]
+fn test_cylinder_and_cone_match_marching() {
+    let center = Vector3::new(1.0, -0.5, 2.0);
+    let cylinder = revolution(vec![
+        Vector2::new(0.0, 0.0),
+        Vector2::new(1.5, 0.0),
+        Vector2::new(1.5, 2.0),
+        Vector2::new(0.0, 2.0),
+    ]);
+    check_against_marching(&Cylinder::new(center, 1.5, 2.0, gray()), &|p| {
+        cylinder(&(p - center))
+    });
+    let cone = revolution(vec![
+        Vector2::new(0.0, 0.0),
+        Vector2::new(1.0, 0.0),
+        Vector2::new(0.0, 3.0),
+    ]);
+    check_against_marching(&Cone::new(center, 1.0, 3.0, gray()), &|p| {
+        cone(&(p - center))
+    });
+}
+
+#[test]
+fn test_capsule_and_torus_match_marching() {
+    let (start, end) = (Vector3::new(-1.0, 0.0, 0.5), Vector3::new(1.0, 2.0, -0.5));
+    let capsule = move |p: &Vector3<f64>| {
+        let axis = end - start;
+        let h = ((p - start).dot(&axis) / axis.norm_squared()).clamp(0.0, 1.0);
+        (p - start - h * axis).norm() - 0.7
+    };
+    check_against_marching(&Capsule::new(start, end, 0.7, gray()), &capsule);
+
+    let center = Vector3::new(0.5, 1.0, 0.0);
+    let torus = move |p: &Vector3<f64>| {
+        let q = p - center;
+        (q.x.hypot(q.z) - 2.0).hypot(q.y) - 0.5
+    };
+    check_against_marching(&Torus::new(center, 2.0, 0.5, gray()), &torus);
+}
+
+#[test]
+fn test_torus_far_away_and_inside() {
+    let torus = Torus::new(Vector3::zeros(), 2.0, 0.5, gray());
+    // through the hole
+    let ray = Ray::new(
+        Vector3::new(0.0, 1e4, 0.0),
+        Vector3::new(0.0, -1.0, 0.0),
+        0.0,
+    );
+    assert!(torus.hit(&ray, 0.001, f64::MAX).is_none());
+    // four hits along the x axis from far away
+    let mut t_min = 0.001;
+    let mut hits = Vec::new();
+    let ray = Ray::new(
+        Vector3::new(-1e4, 0.0, 0.0),
+        Vector3::new(1.0, 0.0, 0.0),
+        0.0,
+    );
+    while let Some(hit) = torus.hit(&ray, t_min, f64::MAX) {
+        hits.push(hit.p.x);
+        t_min = hit.t + 1e-6;
+    }
+    assert_eq!(hits.len(), 4);
+    for (x, expected) in hits.iter().zip([-2.5, -1.5, 1.5, 2.5].iter()) {
+        assert!((x - expected).abs() < 1e-6, "{} {}", x, expected);
+    }
+    // from the inside of the tube the normal points outward
+    let ray = Ray::new(
+        Vector3::new(2.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+        0.0,
+    );
+    let hit = torus.hit(&ray, 0.001, f64::MAX).unwrap();
+    assert!((hit.t - 0.5).abs() < 1e-9);
+    assert!((hit.normal - Vector3::new(0.0, 1.0, 0.0)).norm() < 1e-9);
+    assert!((hit.v - 0.25).abs() < 1e-9);
+}
+
+#[test]
+fn test_sweep_and_caps() {
+    let quarter = Cylinder::new(Vector3::zeros(), 1.0, 1.0, gray()).with_sweep(90.0);
+    // the quarter between the x axis and -z
+    let bbox = quarter.bounding_box(0.0, 1.0).unwrap();
+    assert!(bbox.min.x.abs() < 1e-9 && (bbox.max.x - 1.0).abs() < 1e-9);
+    assert!((bbox.min.z + 1.0).abs() < 1e-9 && bbox.max.z.abs() < 1e-9);
+    for degrees in (5..360).step_by(10) {
+        let phi = (degrees as f64).to_radians();
+        let outside = Vector3::new(3.0 * phi.cos(), 0.5, -3.0 * phi.sin());
+        let ray = Ray::new(
+            outside,
+            -outside.component_mul(&Vector3::new(1.0, 0.0, 1.0)),
+            0.0,
+        );
+        let hit = quarter.hit(&ray, 0.001, f64::MAX);
+        if degrees < 90 {
+            let hit = hit.unwrap();
+            assert!((hit.u - degrees as f64 / 90.0).abs() < 1e-9);
+            assert!((hit.v - 0.5).abs() < 1e-9);
+        } else if degrees > 90 && degrees < 180 || degrees > 270 {
+            // the open cut shows the inside of the other side of the circle
+            // only between 180 and 270 degrees
+            assert!(hit.is_none(), "{}", degrees);
+        } else if degrees > 180 && degrees < 270 {
+            let hit = hit.unwrap();
+            assert!((hit.t - 4.0 / 3.0).abs() < 1e-9);
+        }
+    }
+
+    // down the axis: the caps close the cylinder and the cone
+    let down = Ray::new(
+        Vector3::new(0.1, 5.0, 0.1),
+        Vector3::new(0.0, -1.0, 0.0),
+        0.0,
+    );
+    let cylinder = Cylinder::new(Vector3::zeros(), 1.0, 2.0, gray());
+    let hit = cylinder.hit(&down, 0.001, f64::MAX).unwrap();
+    assert!((hit.t - 3.0).abs() < 1e-9);
+    assert_eq!(hit.normal, Vector3::new(0.0, 1.0, 0.0));
+    let open = Cylinder::new(Vector3::zeros(), 1.0, 2.0, gray()).uncapped();
+    assert!(open.hit(&down, 0.001, f64::MAX).is_none());
+    let cone = Cone::new(Vector3::zeros(), 1.0, 2.0, gray()).uncapped();
+    let hit = cone.hit(&down, 0.001, f64::MAX).unwrap();
+    // the side is at 2 (1 - distance from the axis)
+    assert!((hit.t - (3.0 + 0.2 * 2.0f64.sqrt())).abs() < 1e-9);
+    assert!(cone.hit(&down, hit.t + 1e-6, f64::MAX).is_none());
+}
@@ -0,0 +1,90 @@
+use raytracing_in_rust::*;
+
+use nalgebra::Vector3;
+use std::sync::Mutex;
+
+use crate::camera::Camera;
+use crate::color::PathDepth;
+use crate::hittable::HittableList;
+use crate::material::DiffuseLight;
+use crate::renderer::{RenderSettings, Renderer};
+use crate::sampler::SamplerType;
+use crate::sphere::Sphere;
+use crate::texture::SolidTexture;
+use crate::tonemap::ToneMapping;
+
+fn small_settings() -> RenderSettings {
+    RenderSettings {
+        width: 8,
+        height: 6,
+        samples: 2,
+        depth: PathDepth::new(5),
+        background: Vector3::new(0.2, 0.4, 0.6),
+        seed: None,
+        sampler: SamplerType::default(),
+        tone_mapping: ToneMapping::default(),
+    }
+}
+
+fn camera(settings: &RenderSettings) -> Camera {
+    Camera::new(
+        Vector3::new(0.0, 0.0, 5.0),
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+        40.0,
+        settings.aspect_ratio(),
+        0.0,
+        5.0,
+        0.0,
+        1.0,
+    )
+}
+
+#[test]
+fn test_empty_world_shows_background() {
+    let settings = small_settings();
+    let world = HittableList::default();
+    let frame = Renderer::new(settings).render(&camera(&settings), &world);
+
+    assert_eq!(frame.width(), 8);
+    assert_eq!(frame.height(), 6);
+    assert_eq!(frame.pixels().len(), 48);
+    for pixel in frame.pixels() {
+        assert!((pixel - settings.background).norm() < 1e-12);
+    }
+}
+
+#[test]
+fn test_light_in_front_of_camera() {
+    let settings = small_settings();
+    let mut world = HittableList::default();
+    world.push(Sphere::new(
+        Vector3::new(0.0, 0.0, 0.0),
+        1.0,
+        DiffuseLight::new(SolidTexture::new(1.0, 1.0, 1.0)),
+    ));
+    let frame = Renderer::new(settings).render(&camera(&settings), &world);
+
+    let center = frame.get(4, 3);
+    assert!((center - Vector3::new(1.0, 1.0, 1.0)).norm() < 1e-12);
+    let corner = frame.get(0, 0);
+    assert!((corner - settings.background).norm() < 1e-12);
+
+    let ppm = frame.to_ppm(&settings.tone_mapping);
+    assert!(ppm.starts_with("P3\n8 6\n255\n"));
+    assert_eq!(ppm.lines().count(), 3 + 48);
+}
+
+#[test]
+fn test_progress_reports_every_row() {
+    let settings = small_settings();
+    let world = HittableList::default();
+    let rows = Mutex::new(Vec::new());
+    Renderer::new(settings).render_with_progress(&camera(&settings), &world, |done| {
+        rows.lock().unwrap().push(done)
+    });
+
+    let mut rows = rows.into_inner().unwrap();
+    rows.sort_unstable();
+    assert_eq!(rows, (1..=settings.height).collect::<Vec<_>>());
+}
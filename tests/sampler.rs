@@ -0,0 +1,189 @@
+use raytracing_in_rust::*;
+
+use nalgebra::Vector3;
+use rayon::ThreadPoolBuilder;
+use std::sync::Arc;
+
+use crate::background::Gradient;
+use crate::color::PathDepth;
+use crate::perlin::Perlin;
+use crate::renderer::FrameBuffer;
+use crate::sampler::{IndependentSampler, Sampler, SamplerType};
+use crate::scene::Scene;
+
+// the book cover is the scene with the most randomness: the placement of the
+// spheres, the lens, the motion blur and the three kinds of materials
+fn small_cover(seed: u64, sampler: SamplerType) -> Scene {
+    let mut scene = scenes::random_scene();
+    scene.settings.width = 16;
+    scene.settings.height = 9;
+    scene.settings.samples = 2;
+    scene.settings.depth = PathDepth::new(5);
+    scene.settings.seed = Some(seed);
+    scene.settings.sampler = sampler;
+    // the scene has no lights of its own
+    scene.background = Some(Arc::new(Gradient::sky()));
+    scene
+}
+
+fn render(scene: &Scene, threads: usize) -> FrameBuffer {
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .unwrap();
+    pool.install(|| {
+        scene
+            .renderer()
+            .render(&scene.camera(), scene.world.as_ref())
+    })
+}
+
+const SAMPLERS: [SamplerType; 4] = [
+    SamplerType::Independent,
+    SamplerType::Stratified,
+    SamplerType::Halton,
+    SamplerType::Sobol,
+];
+
+#[test]
+fn test_renders_with_a_seed_are_identical() {
+    for &sampler in SAMPLERS.iter() {
+        let scene = small_cover(42, sampler);
+        let frame = render(&scene, 4);
+        assert_eq!(frame.pixels(), render(&scene, 4).pixels());
+        // the threads do not change the random numbers of a pixel
+        assert_eq!(frame.pixels(), render(&scene, 1).pixels());
+        // neither does a new build of the scene
+        assert_eq!(
+            frame.pixels(),
+            render(&small_cover(42, sampler), 3).pixels()
+        );
+
+        assert_ne!(
+            frame.pixels(),
+            render(&small_cover(43, sampler), 4).pixels()
+        );
+    }
+}
+
+#[test]
+fn test_samplers_restart() {
+    for &sampler_type in SAMPLERS.iter() {
+        let mut sampler = sampler_type.build(7, 16);
+        let mut numbers = |x, y, index| {
+            sampler.start_sample(x, y, index);
+            let mut numbers: Vec<f64> = (0..40).map(|_| sampler.next_1d()).collect();
+            let (u, v) = sampler.next_2d();
+            numbers.extend(&[u, v]);
+            numbers
+        };
+        let first = numbers(3, 5, 2);
+        assert!(first.iter().all(|&r| (0.0..1.0).contains(&r)));
+        assert_eq!(first, numbers(3, 5, 2), "{:?}", sampler_type);
+        assert_ne!(first, numbers(3, 5, 1), "{:?}", sampler_type);
+        assert_ne!(first, numbers(5, 3, 2), "{:?}", sampler_type);
+    }
+}
+
+// the samples of a pixel in one stratum each of a grid of n x m cells
+fn stratified_2d(points: &[(f64, f64)], n: usize, m: usize) -> bool {
+    let mut cells = vec![0; n * m];
+    for &(u, v) in points {
+        cells[(u * n as f64) as usize + (v * m as f64) as usize * n] += 1;
+    }
+    cells.iter().all(|&c| c == points.len() / (n * m))
+}
+
+#[test]
+fn test_samples_are_stratified() {
+    let points = |sampler: &mut dyn Sampler, dimension: usize| {
+        (0..16)
+            .map(|index| {
+                sampler.start_sample(1, 2, index);
+                for _ in 0..dimension {
+                    sampler.next_2d();
+                }
+                sampler.next_2d()
+            })
+            .collect::<Vec<_>>()
+    };
+    for &dimension in [0, 1, 5].iter() {
+        let stratified = points(SamplerType::Stratified.build(3, 16).as_mut(), dimension);
+        assert!(stratified_2d(&stratified, 4, 4));
+        // every draw of the Sobol sampler is a (0, 2)-net
+        let sobol = points(SamplerType::Sobol.build(3, 16).as_mut(), dimension);
+        for &(n, m) in [(16, 1), (8, 2), (4, 4), (2, 8), (1, 16)].iter() {
+            assert!(stratified_2d(&sobol, n, m), "{} {}", n, m);
+        }
+    }
+    // the 1D draws of the stratified sampler, 16 strata
+    let mut sampler = SamplerType::Stratified.build(3, 16);
+    let strata = (0..16)
+        .map(|index| {
+            sampler.start_sample(0, 0, index);
+            (sampler.next_1d(), 0.5)
+        })
+        .collect::<Vec<_>>();
+    assert!(stratified_2d(&strata, 16, 1));
+}
+
+// the area of a quarter of a disk estimated in every pixel: the samplers
+// with stratified points are far more accurate than the independent one
+#[test]
+fn test_samplers_converge_faster() {
+    let error = |sampler_type: SamplerType| {
+        let samples = 64;
+        let mut sampler = sampler_type.build(11, samples);
+        let mut squared_error = 0.0;
+        for pixel in 0..64 {
+            let mut inside = 0;
+            for index in 0..samples {
+                sampler.start_sample(pixel, 0, index);
+                // past the position in the pixel, like the lens
+                sampler.next_2d();
+                let (u, v) = sampler.next_2d();
+                inside += (u * u + v * v < 1.0) as usize;
+            }
+            let estimate = inside as f64 / samples as f64;
+            squared_error += (estimate - std::f64::consts::FRAC_PI_4).powi(2);
+        }
+        (squared_error / 64.0).sqrt()
+    };
+    let independent = error(SamplerType::Independent);
+    for &sampler in SAMPLERS[1..].iter() {
+        let error = error(sampler);
+        assert!(
+            error < independent / 2.0,
+            "{:?} {} {}",
+            sampler,
+            error,
+            independent
+        );
+    }
+}
+
+#[test]
+fn test_independent_sampler_restarts() {
+    let mut sampler = IndependentSampler::new(7);
+    let mut numbers = |x, y, index| {
+        sampler.start_sample(x, y, index);
+        (0..16).map(|_| sampler.next_1d()).collect::<Vec<f64>>()
+    };
+    let first = numbers(3, 5, 0);
+    assert!(first.iter().all(|&r| (0.0..1.0).contains(&r)));
+    assert_eq!(first, numbers(3, 5, 0));
+    assert_ne!(first, numbers(3, 5, 1));
+    assert_ne!(first, numbers(5, 3, 0));
+    assert_ne!(first, {
+        let mut other = IndependentSampler::new(8);
+        other.start_sample(3, 5, 0);
+        (0..16).map(|_| other.next_1d()).collect::<Vec<f64>>()
+    });
+}
+
+#[test]
+fn test_perlin_noise_is_fixed() {
+    let p = Vector3::new(1.3, -2.7, 0.4);
+    assert_eq!(Perlin::new().turb(&p, 7), Perlin::new().turb(&p, 7));
+    assert_ne!(Perlin::new().turb(&p, 7), Perlin::with_seed(1).turb(&p, 7));
+}
@@ -0,0 +1,326 @@
+use raytracing_in_rust::*;
+
+use nalgebra::Vector3;
+
+use crate::hittable::Hittable;
+use crate::ray::Ray;
+use crate::sampler::IndependentSampler;
+
+fn cast(scene: &scene::Scene, origin: Vector3<f64>, direction: Vector3<f64>) -> Option<f64> {
+    let ray = Ray::new(origin, direction, 0.0);
+    scene.world.hit(&ray, 0.001, f64::MAX).map(|hit| hit.t)
+}
+
+#[test]
+fn test_load_cornell_box() {
+    let scene = scene::load("scene/cornell_box.scene").unwrap();
+    assert_eq!(scene.settings.width, 800);
+    assert_eq!(scene.settings.height, 800);
+    assert_eq!(scene.settings.samples, 100);
+    assert_eq!(scene.camera.look_from, Vector3::new(278.0, 278.0, -800.0));
+    assert_eq!(scene.camera.vertical_fov, 40.0);
+
+    // above the boxes: the light, the red wall and the back wall
+    let origin = Vector3::new(278.0, 400.0, 278.0);
+    let ray = Ray::new(origin, Vector3::new(0.0, 1.0, 0.0), 0.0);
+    let hit = scene.world.hit(&ray, 0.001, f64::MAX).unwrap();
+    assert!((hit.t - 154.0).abs() < 1e-9);
+    assert_eq!(
+        hit.material.emitted(hit.u, hit.v, &hit.p),
+        Vector3::new(15.0, 15.0, 15.0)
+    );
+    let t = cast(&scene, origin, Vector3::new(-1.0, 0.0, 0.0)).unwrap();
+    assert!((t - 278.0).abs() < 1e-9);
+    let t = cast(&scene, origin, Vector3::new(0.0, 0.0, 1.0)).unwrap();
+    assert!((t - 277.0).abs() < 1e-9);
+
+    // the tall box is in front of the back wall near the floor
+    let t = cast(
+        &scene,
+        Vector3::new(370.0, 100.0, 0.0),
+        Vector3::new(0.0, 0.0, 1.0),
+    )
+    .unwrap();
+    assert!(t > 250.0 && t < 555.0);
+}
+
+#[test]
+fn test_parse_objects() {
+    let scene = scene::parse(
+        "
+        settings { width 200 height 100 tonemap agx exposure -1 }
+        settings { max_depth 20 min_depth 4 diffuse_depth 2 volume_depth 8 }
+        settings { seed 0 sampler halton }
+        camera { look_from 0 0 10 look_at 0 0 0 fov 30 }
+        texture checker checker { odd 0 0 0 even 1 1 1 }
+        material ground lambertian { albedo checker }
+        material glass dielectric { ior 1.5 }
+        material mirror metal { albedo 0.8 0.8 0.8 fuzz 0.1 }
+        bvh {
+            sphere { center -2 0 0 radius 1 material ground }
+            sphere { center 2 0 0 radius 1 material glass }
+            moving_sphere {
+                center0 0 3 0 center1 0 4 0 time0 0 time1 1
+                radius 0.5 material mirror
+            }
+            flip { rect { plane zx min -1 -1 max 1 1 k -3 material ground } }
+        }
+        medium {
+            density 0.5
+            albedo 1 1 1
+            cube { min -1 -1 -1 max 1 1 1 material glass }
+        }
+        ",
+    )
+    .unwrap();
+    assert_eq!(scene.settings.aspect_ratio(), 2.0);
+    assert_eq!(scene.settings.samples, 100);
+    assert_eq!(
+        scene.settings.tone_mapping.operator,
+        tonemap::ToneMapOperator::AgX
+    );
+    assert_eq!(scene.settings.tone_mapping.exposure, -1.0);
+    assert_eq!(scene.settings.depth.max, 20);
+    assert_eq!(scene.settings.depth.min, 4);
+    assert_eq!(scene.settings.depth.diffuse, 2);
+    assert_eq!(scene.settings.depth.volume, 8);
+    assert_eq!(scene.settings.depth.specular, usize::MAX);
+
+    // russian roulette from the first bounce, without the specular lobe
+    let roulette = scene::parse("settings { min_depth 0 specular_depth 0 }").unwrap();
+    assert_eq!(roulette.settings.depth.min, 0);
+    assert_eq!(roulette.settings.depth.specular, 0);
+    assert_eq!(scene.settings.seed, Some(0));
+    assert_eq!(scene.settings.sampler, sampler::SamplerType::Halton);
+    assert_eq!(scene.camera.vertical_fov, 30.0);
+    scene.camera();
+
+    let down = Vector3::new(0.0, -1.0, 0.0);
+    let t = cast(&scene, Vector3::new(-2.0, 5.0, 0.0), down).unwrap();
+    assert!((t - 4.0).abs() < 1e-9);
+    let t = cast(&scene, Vector3::new(2.0, 5.0, 0.0), down).unwrap();
+    assert!((t - 4.0).abs() < 1e-9);
+    // the rect lies in a BVH, so its bounding box must be on the right axes
+    let t = cast(&scene, Vector3::new(0.5, -2.0, 0.5), down).unwrap();
+    assert!((t - 1.0).abs() < 1e-9);
+    let t = cast(&scene, Vector3::new(0.0, 5.0, 0.0), down).unwrap();
+    assert!((t - 1.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_parse_planes() {
+    let scene = scene::parse(
+        "
+        material m lambertian { albedo 1 1 1 }
+        bvh {
+            plane { point 0 -1 0 normal 0 1 0 material m }
+            quad { corner 0 0 0 u 1 0 0 v 0 1 1 material m }
+            disk { center 5 0 0 normal 1 0 0 radius 2 material m }
+        }
+        ",
+    )
+    .unwrap();
+    let t = cast(
+        &scene,
+        Vector3::new(0.5, 5.0, 0.5),
+        Vector3::new(0.0, -1.0, 0.0),
+    )
+    .unwrap();
+    assert!((t - 4.5).abs() < 1e-9);
+    let t = cast(
+        &scene,
+        Vector3::new(0.0, 1.5, 0.0),
+        Vector3::new(1.0, 0.0, 0.0),
+    )
+    .unwrap();
+    assert!((t - 5.0).abs() < 1e-9);
+    // past the quad and the disk only the plane is left
+    let t = cast(
+        &scene,
+        Vector3::new(100.0, 0.0, 0.0),
+        Vector3::new(0.0, -1.0, 0.0),
+    )
+    .unwrap();
+    assert!((t - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_parse_quadrics() {
+    let scene = scene::parse(
+        "
+        material m lambertian { albedo 1 1 1 }
+        bvh {
+            cylinder { center 0 0 0 radius 1 height 2 sweep 180 uncapped material m }
+            cone { center 5 0 0 radius 1 height 2 material m }
+            capsule { start 10 0 0 end 10 2 0 radius 0.5 material m }
+            torus { center 15 0 0 major 2 minor 0.5 material m }
+        }
+        ",
+    )
+    .unwrap();
+    let down = Vector3::new(0.0, -1.0, 0.0);
+    // the open cylinder is only its side
+    assert!(cast(&scene, Vector3::new(0.0, 5.0, 0.0), down).is_none());
+    let t = cast(&scene, Vector3::new(5.0, 5.0, 0.0), down).unwrap();
+    assert!((t - 3.0).abs() < 1e-9);
+    let t = cast(&scene, Vector3::new(10.0, 5.0, 0.0), down).unwrap();
+    assert!((t - 2.5).abs() < 1e-9);
+    let t = cast(&scene, Vector3::new(17.0, 5.0, 0.0), down).unwrap();
+    assert!((t - 4.5).abs() < 1e-6);
+}
+
+#[test]
+fn test_parse_transform() {
+    let scene = scene::parse(
+        "
+        material m lambertian { albedo 1 1 1 }
+        transform {
+            scale 2 1 1
+            rotate 0 0 1 90
+            translate 0 0 -5
+            sphere { center 0 0 0 radius 1 material m }
+        }
+        ",
+    )
+    .unwrap();
+    // the sphere is stretched along x, then turned to stretch along y
+    let t = cast(
+        &scene,
+        Vector3::new(0.0, 5.0, -5.0),
+        Vector3::new(0.0, -1.0, 0.0),
+    )
+    .unwrap();
+    assert!((t - 3.0).abs() < 1e-9);
+    let t = cast(
+        &scene,
+        Vector3::new(5.0, 0.0, -5.0),
+        Vector3::new(-1.0, 0.0, 0.0),
+    )
+    .unwrap();
+    assert!((t - 4.0).abs() < 1e-9);
+
+    let scene = scene::parse(
+        "
+        material m lambertian { albedo 1 1 1 }
+        animate {
+            key { time 0 }
+            key { time 1 translate 0 2 0 rotate 0 1 0 90 scale 1 2 1 }
+            cube { min -1 -1 -1 max 1 1 1 material m }
+        }
+        ",
+    )
+    .unwrap();
+    let down = |time| {
+        let ray = Ray::new(
+            Vector3::new(0.0, 10.0, 0.0),
+            Vector3::new(0.0, -1.0, 0.0),
+            time,
+        );
+        scene.world.hit(&ray, 0.001, f64::MAX).unwrap().t
+    };
+    assert!((down(0.0) - 9.0).abs() < 1e-9);
+    assert!((down(1.0) - 6.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_parse_instances() {
+    let scene = scene::parse(
+        "
+        material m lambertian { albedo 1 1 1 }
+        material red lambertian { albedo 1 0 0 }
+        prototype ball { sphere { center 0 0 0 radius 1 material m } }
+        instance { prototype ball translate -3 0 0 }
+        instance { prototype ball scale 2 2 2 translate 3 0 0 material red }
+        ",
+    )
+    .unwrap();
+    let mut sampler = IndependentSampler::new(1);
+    let mut down = |x| {
+        let ray = Ray::new(
+            Vector3::new(x, 10.0, 0.0),
+            Vector3::new(0.0, -1.0, 0.0),
+            0.0,
+        );
+        let hit = scene.world.hit(&ray, 0.001, f64::MAX).unwrap();
+        let f = hit
+            .material
+            .sample(&hit, &Vector3::new(0.0, 1.0, 0.0), &mut sampler)
+            .unwrap()
+            .f;
+        (hit.t, f.y)
+    };
+    let (t, green) = down(-3.0);
+    assert!((t - 9.0).abs() < 1e-9);
+    assert!(green > 0.0);
+    let (t, green) = down(3.0);
+    assert!((t - 8.0).abs() < 1e-9);
+    assert_eq!(green, 0.0);
+    // the prototype itself is not drawn
+    let ray = Ray::new(
+        Vector3::new(0.0, 10.0, 0.0),
+        Vector3::new(0.0, -1.0, 0.0),
+        0.0,
+    );
+    assert!(scene.world.hit(&ray, 0.001, f64::MAX).is_none());
+}
+
+#[test]
+fn test_errors_have_positions() {
+    let error = |source: &str| scene::parse(source).err().unwrap();
+
+    let e = error("camera {\n    fov wide\n}");
+    assert_eq!((e.line, e.column), (2, 9));
+    assert!(e
+        .to_string()
+        .contains("<scene>:2:9: 'wide' is not a number"));
+
+    let e = error("material m lambertian { albedo 1 1 1 }\nsphere { center 0 0 0 material m }");
+    assert_eq!((e.line, e.column), (2, 1));
+    assert!(e.message.contains("'radius'"));
+
+    let e = error("sphere { center 0 0 0 radius 1 material nope }");
+    assert_eq!((e.line, e.column), (1, 41));
+
+    let e = error("settings { max_depth 0 }");
+    assert!(e.message.contains("'0' is not a positive integer"));
+    let e = error("settings { volume_depth -1 }");
+    assert_eq!((e.line, e.column), (1, 25));
+    assert!(e.message.contains("'-1' is not a non-negative integer"));
+
+    let e = error("settings { width 10 colour 1 }");
+    assert_eq!((e.line, e.column), (1, 21));
+
+    let e = error("material m lambertian { albedo 1 1 1 }\nflip { sphere { center 0 0 0 radius 1 material m }\n");
+    assert!(e.message.contains("end of the file"));
+    assert_eq!(e.line, 2);
+
+    let e = error("material m lambertian { albedo 1 1 1 }\nanimate { cube { min 0 0 0 max 1 1 1 material m } }");
+    assert!(e.message.contains("at least a key"));
+
+    let e = error("instance { prototype tree }");
+    assert_eq!((e.line, e.column), (1, 22));
+    assert!(e.message.contains("undefined prototype 'tree'"));
+
+    let e = error(
+        "material light diffuse_light { emit 4 4 4 }\n\
+         prototype p { sphere { center 0 0 0 radius 1 material light } }\n\
+         instance { prototype p material light }",
+    );
+    assert_eq!((e.line, e.column), (3, 33));
+    assert!(e.message.contains("cannot be emissive"));
+
+    let e = error("transform { scale 1 0 1 }");
+    assert_eq!((e.line, e.column), (1, 23));
+
+    let e = error(
+        "material m lambertian { albedo 1 1 1 }\nquad { corner 0 0 0 u 1 0 0 v 2 0 0 material m }",
+    );
+    assert_eq!((e.line, e.column), (2, 1));
+    assert!(e.message.contains("parallel"));
+
+    let e = error("texture t image { file \"missing.png\" }");
+    assert_eq!((e.line, e.column), (1, 24));
+
+    let e = scene::load("scene/missing.scene").err().unwrap();
+    assert_eq!(e.line, 0);
+}
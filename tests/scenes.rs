@@ -0,0 +1,42 @@
+use raytracing_in_rust::*;
+
+use nalgebra::Vector3;
+
+use crate::hittable::Hittable;
+use crate::ray::Ray;
+
+#[test]
+fn test_every_name_has_a_scene() {
+    for name in scenes::NAMES.iter() {
+        let scene = scenes::by_name(name).unwrap();
+        assert!(scene.world.bounding_box(0.0, 1.0).is_some(), "{}", name);
+        assert!(scene.settings.width > 0 && scene.settings.samples > 0);
+        scene.camera();
+    }
+    assert!(scenes::by_name("cornell").is_none());
+}
+
+#[test]
+fn test_cornell_box_matches_the_sample_file() {
+    let built_in = scenes::cornell_box();
+    let file = scene::load("scene/cornell_box.scene").unwrap();
+    assert_eq!(built_in.camera.look_from, file.camera.look_from);
+    assert_eq!(built_in.settings.width, file.settings.width);
+
+    // the box is closed: light above, floor below, walls on the sides
+    let origin = Vector3::new(278.0, 400.0, 100.0);
+    for direction in [
+        Vector3::new(0.0, 1.0, 0.0),
+        Vector3::new(0.0, -1.0, 0.0),
+        Vector3::new(1.0, 0.0, 0.0),
+        Vector3::new(-1.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, 1.0),
+        Vector3::new(0.3, 0.8, -0.2),
+    ] {
+        let ray = Ray::new(origin, direction, 0.0);
+        let a = built_in.world.hit(&ray, 0.001, f64::MAX).unwrap();
+        let b = file.world.hit(&ray, 0.001, f64::MAX).unwrap();
+        assert!((a.t - b.t).abs() < 1e-9);
+        assert!((a.normal - b.normal).norm() < 1e-9);
+    }
+}
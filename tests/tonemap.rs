@@ -0,0 +1,72 @@
+use raytracing_in_rust::*;
+
+use nalgebra::Vector3;
+
+use crate::tonemap::{srgb_oetf, ToneMapOperator, ToneMapping};
+
+fn tone_mapping(operator: ToneMapOperator) -> ToneMapping {
+    ToneMapping {
+        operator,
+        ..Default::default()
+    }
+}
+
+fn gray(value: f64) -> Vector3<f64> {
+    Vector3::new(value, value, value)
+}
+
+#[test]
+fn test_srgb_oetf() {
+    assert_eq!(srgb_oetf(0.0), 0.0);
+    assert!((srgb_oetf(1.0) - 1.0).abs() < 1e-12);
+    // the linear segment and the curve meet
+    let knee = 0.003_130_8;
+    assert!((srgb_oetf(knee) - srgb_oetf(knee + 1e-9)).abs() < 1e-6);
+    assert!((srgb_oetf(0.18) - 0.4613).abs() < 1e-4);
+}
+
+#[test]
+fn test_operators_are_monotonic_and_bounded() {
+    for name in ToneMapOperator::NAMES.iter() {
+        let tone_mapping = tone_mapping(ToneMapOperator::from_name(name).unwrap());
+        assert!(tone_mapping.apply(&gray(0.0)).norm() < 0.01, "{}", name);
+        let mut previous = 0.0;
+        for i in 0..200 {
+            let radiance = 0.001 * 1.07f64.powi(i);
+            let color = tone_mapping.apply(&gray(radiance));
+            assert!(color.iter().all(|c| (0.0..=1.0).contains(c)), "{}", name);
+            assert!(color.x >= previous - 1e-9, "{} {}", name, radiance);
+            previous = color.x;
+        }
+        // very bright lights end up (almost) white instead of wrapping around
+        assert!(tone_mapping.apply(&gray(1e4)).x > 0.95, "{}", name);
+        // broken samples are black
+        assert!(tone_mapping
+            .apply(&Vector3::new(f64::NAN, -1.0, 0.0))
+            .iter()
+            .all(|c| c.is_finite()));
+    }
+}
+
+#[test]
+fn test_reinhard_and_exposure() {
+    let reinhard = tone_mapping(ToneMapOperator::Reinhard);
+    assert!((reinhard.apply(&gray(1.0)).x - srgb_oetf(0.5)).abs() < 1e-12);
+    // one stop more is twice the radiance
+    let brighter = ToneMapping {
+        exposure: 1.0,
+        ..reinhard
+    };
+    assert!((brighter.apply(&gray(0.5)).x - srgb_oetf(0.5)).abs() < 1e-12);
+
+    let extended = ToneMapping {
+        white: 8.0,
+        ..tone_mapping(ToneMapOperator::ExtendedReinhard)
+    };
+    assert!((extended.apply(&gray(8.0)).x - 1.0).abs() < 1e-12);
+    assert!(extended.apply(&gray(4.0)).x < 1.0);
+
+    let clamp = tone_mapping(ToneMapOperator::Clamp);
+    assert!((clamp.apply(&Vector3::new(0.5, 2.0, 0.0)).y - 1.0).abs() < 1e-12);
+    assert!((clamp.apply(&gray(0.5)).x - srgb_oetf(0.5)).abs() < 1e-12);
+}
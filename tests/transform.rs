@@ -0,0 +1,297 @@
+use raytracing_in_rust::*;
+
+mod common;
+
+use nalgebra::{Matrix4, Unit, UnitQuaternion, Vector3};
+use std::f64::consts::PI;
+
+use crate::bvh::BVHNode;
+use crate::common::{gray, light};
+use crate::cube::Cube;
+use crate::hittable::{Hittable, HittableList};
+use crate::ray::Ray;
+use crate::rect::{Plane, Rect};
+use crate::rotate::{Axis, Rotate};
+use crate::sampler::{IndependentSampler, Sampler};
+use crate::sphere::Sphere;
+use crate::transform::{rotation, scaling, translation, AnimatedTransform, Keyframe, Transform};
+use crate::traslate::Traslate;
+use crate::util::random_unit_vector;
+
+fn unit_cube() -> Cube {
+    Cube::new(Vector3::repeat(-1.0), Vector3::repeat(1.0), gray())
+}
+
+// rays from random points around the origin toward random points near it
+fn rays(sampler: &mut dyn Sampler) -> Vec<Ray> {
+    (0..500)
+        .map(|_| {
+            let origin = 6.0 * random_unit_vector(sampler);
+            let target = 1.5 * random_unit_vector(sampler);
+            Ray::new(origin, target - origin, 0.0)
+        })
+        .collect()
+}
+
+#[test]
+fn test_matches_translate_and_rotate() {
+    let wrapped = Traslate::new(
+        Rotate::new(Axis::Y, unit_cube(), 30.0),
+        Vector3::new(0.5, -0.2, 0.3),
+    );
+    let transformed = Transform::new(unit_cube(), rotation(&Vector3::y(), 30.0))
+        .then(translation(&Vector3::new(0.5, -0.2, 0.3)));
+    let mut sampler = IndependentSampler::new(1);
+    for ray in rays(&mut sampler) {
+        let expected = wrapped.hit(&ray, 0.001, f64::MAX);
+        let hit = transformed.hit(&ray, 0.001, f64::MAX);
+        assert_eq!(expected.is_some(), hit.is_some());
+        if let (Some(expected), Some(hit)) = (expected, hit) {
+            assert!((expected.t - hit.t).abs() < 1e-9);
+            assert!((expected.p - hit.p).norm() < 1e-9);
+            assert!((expected.normal - hit.normal).norm() < 1e-9);
+        }
+    }
+}
+
+// a unit sphere scaled by (a, b, c) is the ellipsoid (x/a)^2 + (y/b)^2 +
+// (z/c)^2 = 1, with the normal along (x/a^2, y/b^2, z/c^2)
+#[test]
+fn test_non_uniform_scale() {
+    let scale = Vector3::new(2.0, 0.5, 1.0);
+    let turn = rotation(&Vector3::new(1.0, 2.0, 3.0), 40.0);
+    let ellipsoid =
+        Transform::new(Sphere::new(Vector3::zeros(), 1.0, gray()), scaling(&scale)).then(turn);
+    let inverse = turn.try_inverse().unwrap();
+    let mut sampler = IndependentSampler::new(2);
+    let mut hits = 0;
+    for ray in rays(&mut sampler) {
+        if let Some(hit) = ellipsoid.hit(&ray, 0.001, f64::MAX) {
+            hits += 1;
+            assert!((ray.pointing_at(hit.t) - hit.p).norm() < 1e-9);
+            let p = inverse.transform_vector(&hit.p);
+            assert!((p.component_div(&scale).norm_squared() - 1.0).abs() < 1e-9);
+            let normal = turn
+                .transform_vector(&p.component_div(&scale.component_mul(&scale)))
+                .normalize();
+            assert!((hit.normal - normal).norm() < 1e-9);
+        }
+    }
+    assert!(hits > 100);
+}
+
+#[test]
+fn test_bounding_boxes_are_tight() {
+    // a turn of 120 degrees around the diagonal swaps the axes, the cube
+    // goes back to itself
+    let diagonal = Transform::new(unit_cube(), rotation(&Vector3::repeat(1.0), 120.0));
+    let bbox = diagonal.bounding_box(0.0, 1.0).unwrap();
+    assert!((bbox.min - Vector3::repeat(-1.0)).norm() < 1e-9);
+    assert!((bbox.max - Vector3::repeat(1.0)).norm() < 1e-9);
+
+    let turned = Transform::new(unit_cube(), scaling(&Vector3::new(1.0, 3.0, 1.0)))
+        .then(rotation(&Vector3::y(), 45.0))
+        .then(translation(&Vector3::new(0.0, 1.0, 0.0)));
+    let bbox = turned.bounding_box(0.0, 1.0).unwrap();
+    let half = 2.0f64.sqrt();
+    assert!((bbox.min - Vector3::new(-half, -2.0, -half)).norm() < 1e-9);
+    assert!((bbox.max - Vector3::new(half, 4.0, half)).norm() < 1e-9);
+}
+
+#[test]
+fn test_composition() {
+    let a = scaling(&Vector3::new(1.0, 2.0, 0.5));
+    let b = rotation(&Vector3::new(0.0, 1.0, 1.0), 75.0);
+    let c = translation(&Vector3::new(0.1, 0.2, 0.3));
+    let chained = Transform::new(unit_cube(), a).then(b).then(c);
+    let product = Transform::new(unit_cube(), c * b * a);
+    let nested = Transform::new(Transform::new(Transform::new(unit_cube(), a), b), c);
+    assert!((chained.matrix() - product.matrix()).norm() < 1e-12);
+    assert!((chained.matrix() * chained.inverse() - Matrix4::identity()).norm() < 1e-12);
+
+    let mut sampler = IndependentSampler::new(3);
+    for ray in rays(&mut sampler) {
+        let expected = product.hit(&ray, 0.001, f64::MAX).map(|hit| hit.t);
+        let t = nested.hit(&ray, 0.001, f64::MAX).map(|hit| hit.t);
+        assert_eq!(expected.is_some(), t.is_some());
+        if let (Some(expected), Some(t)) = (expected, t) {
+            assert!((expected - t).abs() < 1e-9);
+        }
+    }
+}
+
+// the density of a stretched light integrates to 1 and reaches the light
+#[test]
+fn test_transformed_lights() {
+    let origin = Vector3::new(0.3, -0.2, 0.1);
+    let lights: Vec<Box<dyn Hittable>> = vec![
+        Box::new(
+            Transform::new(
+                Sphere::new(Vector3::zeros(), 1.0, light()),
+                scaling(&Vector3::new(3.0, 0.5, 1.0)),
+            )
+            .then(translation(&Vector3::new(0.0, 3.0, 0.0))),
+        ),
+        Box::new(
+            Transform::new(
+                Rect::new(Plane::XY, -1.0, -1.0, 1.0, 1.0, 0.0, light()),
+                scaling(&Vector3::new(2.0, 0.5, 1.0)),
+            )
+            .then(rotation(&Vector3::new(1.0, 1.0, 0.0), 60.0))
+            .then(translation(&Vector3::new(0.0, 0.0, 2.0))),
+        ),
+    ];
+    let mut sampler = IndependentSampler::new(4);
+    for light in lights.iter() {
+        let mut collected = Vec::new();
+        light.collect_lights(&mut collected);
+        assert_eq!(collected.len(), 1);
+
+        let n = 200_000;
+        let integral = (0..n)
+            .map(|_| light.pdf_value(&origin, &random_unit_vector(&mut sampler)))
+            .sum::<f64>()
+            * 4.0
+            * PI
+            / n as f64;
+        assert!((integral - 1.0).abs() < 0.03, "{}", integral);
+        for _ in 0..100 {
+            let direction = light.random(&origin, &mut sampler);
+            assert!(light.pdf_value(&origin, &direction) > 0.0);
+            assert!(light
+                .hit(&Ray::new(origin, direction, 0.0), 0.001, f64::MAX)
+                .is_some());
+        }
+    }
+}
+
+// still until 0.2, then a turn of 90 degrees around y while it doubles and
+// moves 4 along x until 1
+fn spinning_cube() -> AnimatedTransform<Cube> {
+    let end = Keyframe {
+        translation: Vector3::new(4.0, 0.0, 0.0),
+        rotation: UnitQuaternion::from_axis_angle(&Vector3::y_axis(), PI / 2.0),
+        scale: Vector3::repeat(2.0),
+        ..Keyframe::new(1.0)
+    };
+    AnimatedTransform::new(unit_cube(), vec![end, Keyframe::new(0.2)])
+}
+
+#[test]
+fn test_keyframes_are_interpolated() {
+    let cube = spinning_cube();
+    assert_eq!(cube.keyframes()[0].time, 0.2);
+    let middle = cube.at(0.6);
+    assert!((middle.translation - Vector3::new(2.0, 0.0, 0.0)).norm() < 1e-12);
+    assert!((middle.scale - Vector3::repeat(1.5)).norm() < 1e-12);
+    assert!((middle.rotation.angle() - PI / 4.0).abs() < 1e-12);
+    let axis = middle.rotation.axis().unwrap();
+    assert!((axis.into_inner() - Vector3::y()).norm() < 1e-12);
+    // still outside the keyframes
+    assert_eq!(cube.at(-1.0).translation, Vector3::zeros());
+    assert_eq!(cube.at(2.0).translation, Vector3::new(4.0, 0.0, 0.0));
+
+    // the same ray finds the cube at different places over time
+    let down = |time| {
+        let ray = Ray::new(Vector3::new(4.0, 5.0, 0.0), -Vector3::y(), time);
+        cube.hit(&ray, 0.001, f64::MAX).map(|hit| hit.t)
+    };
+    assert_eq!(down(0.0), None);
+    assert!((down(1.0).unwrap() - 3.0).abs() < 1e-9);
+
+    // at any time it is the static transform of the interpolated keyframe
+    let mut sampler = IndependentSampler::new(5);
+    for ray in rays(&mut sampler) {
+        let ray = Ray::new(ray.origin(), ray.direction(), 0.45);
+        let still = Transform::new(unit_cube(), cube.at(0.45).matrix());
+        let expected = still.hit(&ray, 0.001, f64::MAX);
+        let hit = cube.hit(&ray, 0.001, f64::MAX);
+        assert_eq!(expected.is_some(), hit.is_some());
+        if let (Some(expected), Some(hit)) = (expected, hit) {
+            assert!((expected.t - hit.t).abs() < 1e-9);
+            assert!((expected.p - hit.p).norm() < 1e-9);
+            assert!((expected.normal - hit.normal).norm() < 1e-9);
+        }
+    }
+}
+
+#[test]
+fn test_animated_bounding_box_covers_the_motion() {
+    let mut sampler = IndependentSampler::new(6);
+    let cube = spinning_cube();
+    let bbox = cube.bounding_box(0.0, 1.0).unwrap();
+    for _ in 0..2000 {
+        let time = sampler.next_1d();
+        let origin = 8.0 * random_unit_vector(&mut sampler) + Vector3::new(2.0, 0.0, 0.0);
+        let target = Vector3::new(2.0, 0.0, 0.0) + 3.0 * random_unit_vector(&mut sampler);
+        let ray = Ray::new(origin, target - origin, time);
+        if let Some(hit) = cube.hit(&ray, 0.001, f64::MAX) {
+            assert!(
+                (0..3).all(|a| hit.p[a] >= bbox.min[a] - 1e-9 && hit.p[a] <= bbox.max[a] + 1e-9)
+            );
+        }
+    }
+    // only the still part before 0.2
+    let still = cube.bounding_box(0.0, 0.2).unwrap();
+    assert!((still.min - Vector3::repeat(-1.0)).norm() < 1e-9);
+    assert!((still.max - Vector3::repeat(1.0)).norm() < 1e-9);
+
+    // a move without rotation is bounded by its two ends
+    let slide = AnimatedTransform::new(
+        unit_cube(),
+        vec![
+            Keyframe::new(0.0),
+            Keyframe {
+                translation: Vector3::new(0.0, 3.0, 0.0),
+                ..Keyframe::new(1.0)
+            },
+        ],
+    );
+    let bbox = slide.bounding_box(0.0, 1.0).unwrap();
+    assert!((bbox.min - Vector3::repeat(-1.0)).norm() < 1e-9);
+    assert!((bbox.max - Vector3::new(1.0, 4.0, 1.0)).norm() < 1e-9);
+}
+
+// a BVH with moving objects finds the same hits as a list at any time
+#[test]
+fn test_animated_objects_in_a_bvh() {
+    let spin = |offset: f64| -> AnimatedTransform<Cube> {
+        let axis = Unit::new_normalize(Vector3::new(1.0, 1.0, offset));
+        AnimatedTransform::new(
+            Cube::new(
+                Vector3::new(0.5, -0.2, -0.2),
+                Vector3::new(1.5, 0.2, 0.2),
+                gray(),
+            ),
+            vec![
+                Keyframe {
+                    translation: Vector3::new(offset, 0.0, 0.0),
+                    ..Keyframe::new(0.0)
+                },
+                Keyframe {
+                    translation: Vector3::new(offset, 1.0, 0.0),
+                    rotation: UnitQuaternion::from_axis_angle(&axis, 2.5),
+                    ..Keyframe::new(1.0)
+                },
+            ],
+        )
+    };
+    let mut list = HittableList::default();
+    let mut objects: Vec<std::sync::Arc<dyn Hittable>> = Vec::new();
+    for i in 0..8 {
+        let offset = i as f64 * 0.7 - 2.5;
+        list.push(spin(offset));
+        objects.push(std::sync::Arc::new(spin(offset)));
+    }
+    let bvh = BVHNode::from_list(objects, 0.0, 1.0);
+    let mut sampler = IndependentSampler::new(7);
+    for _ in 0..2000 {
+        let time = sampler.next_1d();
+        let origin = 8.0 * random_unit_vector(&mut sampler);
+        let target = 2.0 * random_unit_vector(&mut sampler);
+        let ray = Ray::new(origin, target - origin, time);
+        let expected = list.hit(&ray, 0.001, f64::MAX).map(|hit| hit.t);
+        let t = bvh.hit(&ray, 0.001, f64::MAX).map(|hit| hit.t);
+        assert_eq!(expected, t);
+    }
+}